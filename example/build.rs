@@ -1,11 +1,7 @@
-use prost_wkt_build::*;
-use std::{env, path::PathBuf};
-
 fn main() {
-    let out = PathBuf::from(env::var("OUT_DIR").unwrap());
-    let descriptor_file = out.join("descriptors.bin");
-    let mut prost_build = prost_build::Config::new();
-    prost_build
+    let mut builder = prost_wkt_build::Builder::new();
+    builder
+        .config_mut()
         .type_attribute(
             ".my.requests",
             "#[derive(serde::Serialize, serde::Deserialize)] #[serde(default, rename_all=\"camelCase\")]",
@@ -14,19 +10,62 @@ fn main() {
             ".my.messages.Foo",
             "#[derive(serde::Serialize, serde::Deserialize)] #[serde(default, rename_all=\"camelCase\")]",
         )
+        .type_attribute(
+            ".LegacyEvent",
+            "#[derive(serde::Serialize, serde::Deserialize)] #[serde(default, rename_all=\"camelCase\")]",
+        )
         .type_attribute(
             ".my.messages.Content",
             "#[derive(serde::Serialize, serde::Deserialize)] #[serde(rename_all=\"camelCase\")]",
+        );
+    builder
+        .compile_protos(
+            &[
+                "proto/messages.proto",
+                "proto/requests.proto",
+                "proto/legacy.proto",
+            ],
+            &["proto/"],
+        )
+        .unwrap();
+
+    let mut builder = prost_wkt_build::Builder::new();
+    builder
+        .serde_options(
+            prost_wkt_build::SerdeOptions::default()
+                .with_proto3_json(true)
+                .with_file_descriptor_set(true),
         )
-        .extern_path(".google.protobuf.Any", "::prost_wkt_types::Any")
-        .extern_path(".google.protobuf.Timestamp", "::prost_wkt_types::Timestamp")
-        .extern_path(".google.protobuf.Value", "::prost_wkt_types::Value")
-        .file_descriptor_set_path(&descriptor_file)
-        .compile_protos(&["proto/messages.proto", "proto/requests.proto"], &["proto/"])
+        .compile_protos(&["proto/json.proto"], &["proto/"])
         .unwrap();
 
-    let descriptor_bytes = std::fs::read(descriptor_file).unwrap();
-    let descriptor = FileDescriptorSet::decode(&descriptor_bytes[..]).unwrap();
+    let mut builder = prost_wkt_build::Builder::new();
+    builder
+        .config_mut()
+        .skip_debug([".my.names.Quiet"])
+        .type_attribute(
+            ".my.names",
+            "#[derive(serde::Serialize, serde::Deserialize)] #[serde(default)]",
+        );
+    builder
+        .serde_options(
+            prost_wkt_build::SerdeOptions::default()
+                .with_prost_type_names(true)
+                .with_static_registry(true),
+        )
+        .compile_protos(&["proto/names.proto"], &["proto/"])
+        .unwrap();
 
-    prost_wkt_build::add_serde(out, descriptor);
+    let mut builder = prost_wkt_build::Builder::new();
+    builder.config_mut().prost_path("crate::sdk::prost");
+    builder
+        .serde_options(
+            prost_wkt_build::SerdeOptions::default()
+                .with_proto3_json(true)
+                .with_prost_path("crate::sdk::prost")
+                .with_prost_wkt_path("crate::sdk::prost_wkt")
+                .with_prost_wkt_types_path("crate::sdk::prost_wkt_types"),
+        )
+        .compile_protos(&["proto/reexport.proto"], &["proto/"])
+        .unwrap();
 }
@@ -34,5 +34,6 @@ fn main() -> Result<(), AnyError> {
             .expect("Failed to downcast message");
         println!("Unpacked: {unpacked_foo:?}");
     }
+
     Ok(())
 }
@@ -0,0 +1,36 @@
+use prost_wkt::MessageSerde;
+
+include!(concat!(env!("OUT_DIR"), "/my.json.rs"));
+
+#[test]
+fn embedded_file_descriptor_set() {
+    let files: Vec<&str> = FILE_DESCRIPTOR_SET
+        .file_descriptor_set()
+        .file
+        .iter()
+        .map(|file| file.name())
+        .collect();
+    assert_eq!(files, vec!["json.proto"]);
+    assert!(!FILE_DESCRIPTOR_SET.encoded().is_empty());
+}
+
+#[test]
+fn message_descriptors() {
+    let descriptor = Sample::default().descriptor().unwrap();
+    assert_eq!(descriptor.file().name(), "json.proto");
+    assert_eq!(descriptor.message().name(), "Sample");
+
+    let inner = sample::Inner::default().descriptor().unwrap();
+    assert_eq!(inner.message().name(), "Inner");
+    assert_eq!(inner.file().package(), "my.json");
+}
+
+#[test]
+fn registered_decoders_expose_descriptors() {
+    let entry = prost_wkt::inventory::iter::<prost_wkt::MessageSerdeDecoderEntry>
+        .into_iter()
+        .find(|entry| entry.type_url == "type.googleapis.com/my.json.Sample.Inner")
+        .unwrap();
+    let descriptor = entry.descriptor().unwrap();
+    assert_eq!(descriptor.message().name(), "Inner");
+}
@@ -0,0 +1,42 @@
+use prost_wkt::MessageSerde;
+use prost_wkt_types::*;
+
+include!(concat!(env!("OUT_DIR"), "/my.names.rs"));
+
+#[test]
+fn clone_compare_and_downcast_boxed_messages() {
+    let named = Named {
+        label: "boxed".to_string(),
+    };
+    let any = Any::pack_from(&named).unwrap();
+    let unpacked: Box<dyn MessageSerde> = any.try_unpack().unwrap();
+
+    let copy = unpacked.clone();
+    assert!(*copy == *unpacked);
+    assert_eq!(format!("{copy:?}"), format!("{named:?}"));
+
+    let other: Box<dyn MessageSerde> = Box::new(Named {
+        label: "other".to_string(),
+    });
+    assert!(*other != *unpacked);
+    let part: Box<dyn MessageSerde> = Box::new(named::Part { index: 0 });
+    assert!(!part.eq_dyn(unpacked.as_ref()));
+
+    let part = part.downcast::<Named>().unwrap_err();
+    assert_eq!(
+        *part.downcast::<named::Part>().unwrap(),
+        named::Part { index: 0 }
+    );
+    let back: Box<Named> = unpacked.downcast::<Named>().unwrap();
+    assert_eq!(*back, named);
+}
+
+#[test]
+fn debug_without_debug_impl_shows_type_url() {
+    let quiet: Box<dyn MessageSerde> = Box::new(Quiet {
+        secret: "hidden".to_string(),
+    });
+    let debug = format!("{quiet:?}");
+    assert!(debug.contains("type.googleapis.com/my.names.Quiet"));
+    assert!(!debug.contains("hidden"));
+}
@@ -0,0 +1,20 @@
+use prost::Name;
+use prost_wkt::MessageSerde;
+use prost_wkt_types::*;
+
+include!(concat!(env!("OUT_DIR"), "/my.messages.rs"));
+
+#[test]
+fn pack_and_unpack_nested_message() {
+    let detail = foo::Detail {
+        note: "Nested message".to_string(),
+    };
+    let any = Any::try_pack(detail.clone()).unwrap();
+    assert_eq!(any.type_url, "type.googleapis.com/my.messages.Foo.Detail");
+    assert_eq!(MessageSerde::message_name(&detail), "Detail");
+    assert_eq!(MessageSerde::package_name(&detail), "my.messages");
+    assert_eq!(<foo::Detail as Name>::full_name(), "my.messages.Foo.Detail");
+
+    let unpacked: Box<dyn MessageSerde> = any.try_unpack().unwrap();
+    assert_eq!(unpacked.downcast_ref::<foo::Detail>(), Some(&detail));
+}
@@ -0,0 +1,23 @@
+use prost_wkt::MessageSerde;
+use prost_wkt_types::*;
+
+include!(concat!(env!("OUT_DIR"), "/_.rs"));
+
+#[test]
+fn pack_and_serialize_message_without_package() {
+    let event = LegacyEvent {
+        name: "No package".to_string(),
+    };
+    let any = Any::try_pack(event.clone()).unwrap();
+    assert_eq!(any.type_url, "type.googleapis.com/LegacyEvent");
+    assert_eq!(MessageSerde::package_name(&event), "");
+
+    let json = serde_json::to_value(&any).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "@type": "type.googleapis.com/LegacyEvent", "name": "No package" })
+    );
+    let back: Any = serde_json::from_value(json).unwrap();
+    let unpacked: Box<dyn MessageSerde> = back.try_unpack().unwrap();
+    assert_eq!(unpacked.downcast_ref::<LegacyEvent>(), Some(&event));
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use prost_wkt_types::*;
+use serde_json::json;
+
+include!(concat!(env!("OUT_DIR"), "/my.json.rs"));
+
+fn create_sample() -> Sample {
+    let mut blobs = HashMap::new();
+    blobs.insert("blob".to_string(), vec![0xfb, 0xff]);
+    let mut counters = HashMap::new();
+    counters.insert(7, i64::MAX);
+    let mut states = HashMap::new();
+    states.insert("now".to_string(), Status::Active as i32);
+
+    Sample {
+        display_name: "sample".to_string(),
+        big_number: -9007199254740993,
+        big_unsigned: u64::MAX,
+        payload: b"hello".to_vec(),
+        ratio: f64::INFINITY,
+        ids: vec![1, 2],
+        blobs,
+        maybe: Some(0),
+        status: Status::Active as i32,
+        created: Some(Timestamp {
+            seconds: 99,
+            nanos: 42,
+        }),
+        extra: Some(Value::null()),
+        inner: Some(sample::Inner {
+            scores: vec![0.5, f32::NAN],
+            counters,
+            history: vec![Status::Unspecified as i32, 7],
+            states,
+            level: sample::inner::Level::High as i32,
+        }),
+        r#type: "kind".to_string(),
+        renamed: 3,
+        empty: Some(Empty {}),
+        choice: Some(sample::Choice::Number(5)),
+    }
+}
+
+#[test]
+fn serialize_proto3_json() {
+    let json = serde_json::to_value(create_sample()).unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "displayName": "sample",
+            "bigNumber": "-9007199254740993",
+            "bigUnsigned": "18446744073709551615",
+            "payload": "aGVsbG8=",
+            "ratio": "Infinity",
+            "ids": ["1", "2"],
+            "blobs": { "blob": "+/8=" },
+            "maybe": 0,
+            "status": "STATUS_ACTIVE",
+            "created": "1970-01-01T00:01:39.000000042Z",
+            "extra": null,
+            "inner": {
+                "scores": [0.5, "NaN"],
+                "counters": { "7": "9223372036854775807" },
+                "history": ["STATUS_UNSPECIFIED", 7],
+                "states": { "now": "STATUS_ACTIVE" },
+                "level": "LEVEL_HIGH"
+            },
+            "type": "kind",
+            "customName": 3,
+            "empty": {},
+            "number": "5"
+        })
+    );
+}
+
+#[test]
+fn omit_default_values() {
+    let json = serde_json::to_value(Sample::default()).unwrap();
+    assert_eq!(json, json!({}));
+}
+
+#[test]
+fn deserialize_proto3_json() {
+    let sample = create_sample();
+    let json = serde_json::to_string(&sample).unwrap();
+    let back: Sample = serde_json::from_str(&json).unwrap();
+    let inner = back.inner.as_ref().unwrap();
+    assert!(inner.scores[1].is_nan());
+    assert_eq!(inner.counters, sample.inner.as_ref().unwrap().counters);
+    assert_eq!(inner.history, sample.inner.as_ref().unwrap().history);
+    assert_eq!(inner.states, sample.inner.as_ref().unwrap().states);
+    assert_eq!(
+        Sample {
+            inner: None,
+            ..back
+        },
+        Sample {
+            inner: None,
+            ..sample
+        }
+    );
+}
+
+#[test]
+fn deserialize_proto_names_and_nulls() {
+    let back: Sample = serde_json::from_value(json!({
+        "display_name": "proto name",
+        "big_number": 12,
+        "renamed": 4,
+        "payload": "aGVsbG8",
+        "ids": null,
+        "inner": null,
+        "extra": null,
+        "unknownField": true
+    }))
+    .unwrap();
+    assert_eq!(
+        back,
+        Sample {
+            display_name: "proto name".to_string(),
+            big_number: 12,
+            renamed: 4,
+            payload: b"hello".to_vec(),
+            extra: Some(Value::null()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn deserialize_enum_names_and_numbers() {
+    let back: Sample = serde_json::from_value(json!({
+        "status": 1,
+        "inner": { "history": ["STATUS_ACTIVE", 0, 42], "level": null }
+    }))
+    .unwrap();
+    assert_eq!(back.status, Status::Active as i32);
+    let inner = back.inner.unwrap();
+    assert_eq!(inner.history, vec![1, 0, 42]);
+    assert_eq!(inner.level, 0);
+
+    let error = serde_json::from_value::<Sample>(json!({ "status": "STATUS_UNKNOWN" }))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("STATUS_UNKNOWN"));
+}
+
+#[test]
+fn oneof_members_are_flattened() {
+    let sample = Sample {
+        choice: Some(sample::Choice::Text(String::new())),
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&sample).unwrap();
+    assert_eq!(json, json!({ "text": "" }));
+
+    let back: Sample = serde_json::from_value(json).unwrap();
+    assert_eq!(back, sample);
+
+    let unset: Sample = serde_json::from_value(json!({ "number": null })).unwrap();
+    assert_eq!(unset.choice, None);
+}
+
+#[test]
+fn reject_multiple_oneof_members() {
+    let error = serde_json::from_value::<Sample>(json!({ "text": "a", "number": "1" }))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("more than one field of oneof `choice` is set"));
+}
+
+#[test]
+fn serialize_oneof_on_its_own() {
+    let choice = sample::Choice::Number(-1);
+    let json = serde_json::to_value(&choice).unwrap();
+    assert_eq!(json, json!({ "number": "-1" }));
+
+    let back: sample::Choice = serde_json::from_value(json).unwrap();
+    assert_eq!(back, choice);
+    assert!(serde_json::from_value::<sample::Choice>(json!({})).is_err());
+}
+
+#[test]
+fn wrappers_and_empty() {
+    let mut blobs = HashMap::new();
+    blobs.insert("blob".to_string(), vec![1, 2]);
+    let wrappers = Wrappers {
+        big: Some(5),
+        big_unsigned: Some(u64::MAX),
+        small: Some(0),
+        ratio: Some(f64::NAN),
+        score: Some(0.5),
+        payload: Some(vec![1, 2]),
+        flag: Some(false),
+        label: Some(String::new()),
+        history: vec![1, -1],
+        blobs,
+        nothing: Some(()),
+        choice: Some(wrappers::Choice::None(())),
+    };
+
+    let json = serde_json::to_value(&wrappers).unwrap();
+    assert_eq!(
+        json,
+        json!({
+            "big": "5",
+            "bigUnsigned": "18446744073709551615",
+            "small": 0,
+            "ratio": "NaN",
+            "score": 0.5,
+            "payload": "AQI=",
+            "flag": false,
+            "label": "",
+            "history": ["1", "-1"],
+            "blobs": { "blob": "AQI=" },
+            "nothing": {},
+            "none": {},
+        })
+    );
+
+    let back: Wrappers = serde_json::from_value(json).unwrap();
+    assert_eq!(back.big, Some(5));
+    assert!(back.ratio.unwrap().is_nan());
+    assert_eq!(
+        Wrappers {
+            ratio: None,
+            ..back
+        },
+        Wrappers {
+            ratio: None,
+            ..wrappers
+        }
+    );
+}
+
+#[test]
+fn deserialize_wrappers_and_empty() {
+    let wrappers: Wrappers =
+        serde_json::from_value(json!({ "big": "5", "nothing": {}, "amount": 7 })).unwrap();
+    assert_eq!(wrappers.big, Some(5));
+    assert_eq!(wrappers.nothing, Some(()));
+    assert_eq!(wrappers.choice, Some(wrappers::Choice::Amount(7)));
+
+    let wrappers: Wrappers =
+        serde_json::from_value(json!({ "big": null, "nothing": null })).unwrap();
+    assert_eq!(wrappers, Wrappers::default());
+    assert_eq!(serde_json::to_value(&wrappers).unwrap(), json!({}));
+}
@@ -0,0 +1,59 @@
+use prost_wkt_types::*;
+use serde_json::json;
+
+// Stands in for a framework crate that re-exports `prost`, `prost_wkt` and `prost_wkt_types`.
+mod sdk {
+    pub use prost;
+    pub use prost_wkt;
+    pub use prost_wkt_types;
+}
+
+include!(concat!(env!("OUT_DIR"), "/my.reexport.rs"));
+
+// This crate depends on the crates directly, so compiling the generated code does not
+// prove that it only uses the configured paths. Check the source for absolute paths instead.
+fn assert_only_sdk_paths(code: &str, krate: &str) {
+    for path in [format!(":: {krate} ::"), format!("::{krate}::")] {
+        for (index, _) in code.match_indices(&path) {
+            let before = code[..index].trim_end();
+            assert!(
+                before.ends_with("sdk"),
+                "generated code uses `::{krate}` directly: {}",
+                &code[index.saturating_sub(40)..(index + 40).min(code.len())]
+            );
+        }
+    }
+}
+
+#[test]
+fn generated_code_has_no_absolute_crate_paths() {
+    for file in ["my.reexport.rs", "my.reexport.serde.rs"] {
+        let code =
+            std::fs::read_to_string(std::path::Path::new(env!("OUT_DIR")).join(file)).unwrap();
+        assert_only_sdk_paths(&code, "prost");
+        assert_only_sdk_paths(&code, "prost_wkt");
+        assert_only_sdk_paths(&code, "prost_wkt_types");
+    }
+}
+
+#[test]
+fn generated_code_uses_configured_crate_paths() {
+    let wrapped = Wrapped {
+        id: "abc".to_string(),
+        count: 3,
+        level: Level::High as i32,
+        at: Some(Timestamp {
+            seconds: 1,
+            nanos: 0,
+        }),
+    };
+    assert_eq!(
+        serde_json::to_value(&wrapped).unwrap(),
+        json!({ "id": "abc", "count": "3", "level": "LEVEL_HIGH", "at": "1970-01-01T00:00:01Z" })
+    );
+
+    let any = Any::try_pack(wrapped.clone()).unwrap();
+    assert_eq!(any.type_url, "type.googleapis.com/my.reexport.Wrapped");
+    let unpacked = any.try_unpack().unwrap();
+    assert_eq!(unpacked.downcast_ref::<Wrapped>(), Some(&wrapped));
+}
@@ -0,0 +1,30 @@
+use prost_wkt::registry::{self, Registry};
+use prost_wkt_types::*;
+
+include!(concat!(env!("OUT_DIR"), "/my.names.rs"));
+
+#[test]
+fn any_uses_installed_registry() {
+    let registry = Registry::new().with_entries(MESSAGE_SERDE_ENTRIES);
+    assert!(registry::install(registry).is_ok());
+
+    let type_url = "type.googleapis.com/my.names.Named";
+    let entry = registry::find(type_url).unwrap();
+    assert!(MESSAGE_SERDE_ENTRIES
+        .iter()
+        .any(|table_entry| std::ptr::eq(table_entry, entry)));
+    assert!(registry::find("type.googleapis.com/my.names.Named.Part").is_some());
+
+    let named = Named {
+        label: "static".to_string(),
+    };
+    let any = Any::try_pack(named.clone()).unwrap();
+    let json = serde_json::to_string(&any).unwrap();
+    let back: Any = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, any);
+
+    let unpacked = back.try_unpack().unwrap();
+    assert_eq!(unpacked.downcast_ref::<Named>(), Some(&named));
+
+    assert!(registry::install(Registry::new()).is_err());
+}
@@ -0,0 +1,39 @@
+use prost::Name;
+use prost_wkt::MessageSerde;
+use prost_wkt_types::*;
+
+include!(concat!(env!("OUT_DIR"), "/my.names.rs"));
+
+#[test]
+fn type_url_comes_from_prost_name() {
+    let named = Named {
+        label: "prost".to_string(),
+    };
+    assert_eq!(
+        <Named as Name>::type_url(),
+        "type.googleapis.com/my.names.Named"
+    );
+    assert_eq!(MessageSerde::type_url(&named), <Named as Name>::type_url());
+
+    let part = named::Part { index: 1 };
+    assert_eq!(<named::Part as Name>::full_name(), "my.names.Named.Part");
+    assert_eq!(
+        MessageSerde::type_url(&part),
+        <named::Part as Name>::type_url()
+    );
+}
+
+#[test]
+fn pack_and_unpack_with_prost_name() {
+    let named = Named {
+        label: "prost".to_string(),
+    };
+    let any = Any::try_pack(named.clone()).unwrap();
+    assert_eq!(any.type_url, <Named as Name>::type_url());
+
+    let json = serde_json::to_string(&any).unwrap();
+    let back: Any = serde_json::from_str(&json).unwrap();
+    let unpacked = back.clone().try_unpack().unwrap();
+    assert_eq!(unpacked.downcast_ref::<Named>(), Some(&named));
+    assert_eq!(back.unpack_as(Named::default()).unwrap(), named);
+}
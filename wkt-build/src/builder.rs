@@ -0,0 +1,548 @@
+use std::env;
+use std::fs;
+use std::io::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use prost::Message;
+use prost_types::FileDescriptorSet;
+
+use crate::{gen_serde, selected_type_urls, SerdeOptions};
+
+/// The `google.protobuf` types that are provided by `prost-wkt-types`. `Empty` is left out as
+/// `prost-build` already maps it to `()`.
+const WELL_KNOWN_TYPES: &[&str] = &[
+    "Any",
+    "Duration",
+    "FieldMask",
+    "ListValue",
+    "NullValue",
+    "Struct",
+    "Timestamp",
+    "Value",
+];
+
+/// Compiles `.proto` files with `prost-build` and adds the `prost-wkt` serde support in one go.
+///
+/// The builder wraps a [`prost_build::Config`] and maps all the well known types to
+/// `prost_wkt_types` (see [`SerdeOptions::with_prost_wkt_types_path`]) when compiling, so do not
+/// add an `extern_path` for them yourself. The file descriptor set produced by `protoc` is fed to
+/// the serde step after compilation; set [`Builder::file_descriptor_set_path`] to keep it.
+///
+/// The serde code is only generated for the types prost generates code for: imported
+/// `google.protobuf` files and the types added with [`Builder::extern_path`] are skipped.
+///
+/// # Example `build.rs`
+///
+/// ```rust,no_run
+/// fn main() -> std::io::Result<()> {
+///     let mut builder = prost_wkt_build::Builder::new();
+///     builder
+///         .config_mut()
+///         .type_attribute(".", "#[derive(serde::Serialize,serde::Deserialize)]");
+///     builder.compile_protos(&["proto/messages.proto"], &["proto/"])
+/// }
+/// ```
+pub struct Builder {
+    config: prost_build::Config,
+    options: SerdeOptions,
+    out_dir: Option<PathBuf>,
+    file_descriptor_set_path: Option<PathBuf>,
+    extern_paths: Vec<String>,
+    well_known_types_mapped: bool,
+}
+
+impl Builder {
+    /// Creates a builder around a default [`prost_build::Config`].
+    pub fn new() -> Self {
+        Self::with_config(prost_build::Config::new())
+    }
+
+    /// Creates a builder around the given [`prost_build::Config`].
+    pub fn with_config(config: prost_build::Config) -> Self {
+        Self {
+            config,
+            options: SerdeOptions::default(),
+            out_dir: None,
+            file_descriptor_set_path: None,
+            extern_paths: vec![".google.protobuf".to_string()],
+            well_known_types_mapped: false,
+        }
+    }
+
+    /// Returns the wrapped [`prost_build::Config`] to add further code generation options.
+    pub fn config_mut(&mut self) -> &mut prost_build::Config {
+        &mut self.config
+    }
+
+    /// Maps the proto path to a Rust path of another crate, see
+    /// [`prost_build::Config::extern_path`]. No serde code is generated for the types under the
+    /// path, so use this instead of adding the `extern_path` to [`Builder::config_mut`].
+    pub fn extern_path<P1, P2>(&mut self, proto_path: P1, rust_path: P2) -> &mut Self
+    where
+        P1: Into<String>,
+        P2: Into<String>,
+    {
+        let proto_path = proto_path.into();
+        self.config.extern_path(proto_path.clone(), rust_path);
+        self.extern_paths.push(proto_path);
+        self
+    }
+
+    /// Sets the options for the generated `MessageSerde` implementations.
+    pub fn serde_options(&mut self, options: SerdeOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the output directory of the generated code. Defaults to the `OUT_DIR` environment
+    /// variable.
+    pub fn out_dir<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.out_dir = Some(path.into());
+        self
+    }
+
+    /// Sets the path the file descriptor set is written to.
+    ///
+    /// This is the supported way to keep the file descriptor set, as the builder reads it back
+    /// for the serde step. A path set with `prost_build::Config::file_descriptor_set_path` is
+    /// used as well, e.g. together with `skip_protoc_run`. Without either, the set is written to
+    /// a temporary file in the output directory that is removed after compilation.
+    pub fn file_descriptor_set_path<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.file_descriptor_set_path = Some(path.into());
+        self
+    }
+
+    /// Compiles the `.proto` files and adds the serde support to the generated code.
+    pub fn compile_protos(
+        &mut self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<()> {
+        let configured = match self.file_descriptor_set_path {
+            Some(ref path) => Some(path.clone()),
+            None => debug_field(&self.config, "file_descriptor_set_path").map(PathBuf::from),
+        };
+        let file_descriptor_set_path = match configured {
+            Some(ref path) => path.clone(),
+            None => temporary_path(&self.target()?),
+        };
+        self.config
+            .file_descriptor_set_path(&file_descriptor_set_path);
+
+        let encoded = self
+            .config
+            .load_fds(protos, includes)
+            .and_then(|_| fs::read(&file_descriptor_set_path));
+        if configured.is_none() {
+            // The file may not have been written if protoc failed.
+            let _ = fs::remove_file(&file_descriptor_set_path);
+        }
+        self.compile_encoded_fds(&encoded?)
+    }
+
+    /// Compiles an encoded file descriptor set, as written by `protoc --descriptor_set_out`, and
+    /// adds the serde support to the generated code. Unlike [`Builder::compile_fds`] the type url
+    /// generator can read custom options from
+    /// [`TypeUrlContext::encoded_file`](crate::TypeUrlContext::encoded_file).
+    pub fn compile_encoded_fds(&mut self, encoded: &[u8]) -> Result<()> {
+        let descriptor = FileDescriptorSet::decode(encoded).map_err(Error::other)?;
+        self.compile(descriptor, Some(encoded))
+    }
+
+    /// Compiles an already loaded file descriptor set and adds the serde support to the generated
+    /// code. Useful when the descriptors are produced by something other than `protoc`.
+    pub fn compile_fds(&mut self, descriptor: FileDescriptorSet) -> Result<()> {
+        self.compile(descriptor, None)
+    }
+
+    fn compile(&mut self, descriptor: FileDescriptorSet, encoded: Option<&[u8]>) -> Result<()> {
+        let out_dir = self.target()?;
+        if !self.well_known_types_mapped {
+            // prost rejects duplicate extern paths, so the types are only mapped once.
+            for name in WELL_KNOWN_TYPES {
+                self.config.extern_path(
+                    format!(".google.protobuf.{name}"),
+                    format!("{}::{name}", self.options.prost_wkt_types_path),
+                );
+            }
+            self.well_known_types_mapped = true;
+        }
+        if self.options.prost_type_names {
+            // prost's `Name` impls have to produce the generated type urls, so their domains are
+            // derived from the urls.
+            self.config.enable_type_names();
+            for (fq_path, type_url) in
+                selected_type_urls(&descriptor, encoded, &self.options, &self.extern_paths)
+            {
+                let domain = type_url
+                    .strip_suffix(&fq_path[1..])
+                    .and_then(|domain| domain.strip_suffix('/'))
+                    .ok_or_else(|| {
+                        Error::other(format!(
+                            "the type url `{type_url}` of `{}` does not end with its full name, \
+                             which prost's `Name` impl requires",
+                            &fq_path[1..]
+                        ))
+                    })?;
+                self.config.type_name_domain([&fq_path], domain);
+            }
+        }
+        let default_package_filename = match self.options.default_package_filename {
+            Some(ref filename) => {
+                self.config.default_package_filename(filename);
+                filename.clone()
+            }
+            None => debug_field(&self.config, "default_package_filename")
+                .unwrap_or_else(|| "_".to_string()),
+        };
+        self.config
+            .out_dir(&out_dir)
+            .compile_fds(descriptor.clone())?;
+
+        gen_serde(
+            &out_dir,
+            &descriptor,
+            encoded,
+            &self.options,
+            &default_package_filename,
+            &self.extern_paths,
+        )?;
+        Ok(())
+    }
+
+    fn target(&self) -> Result<PathBuf> {
+        match self.out_dir {
+            Some(ref out_dir) => Ok(out_dir.clone()),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| Error::other("OUT_DIR environment variable is not set")),
+        }
+    }
+}
+
+// Returns a path in the output directory for a file descriptor set that is removed after use.
+// Builders in the same build script get different paths.
+fn temporary_path(out_dir: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    out_dir.join(format!(
+        ".prost-wkt-descriptors-{}-{count}.bin",
+        std::process::id()
+    ))
+}
+
+// prost-build has no getters for its settings, so the ones the builder has to agree with are read
+// back from the `Debug` output of the config. Returns the string value of the field, unwrapping a
+// `Some`, or `None` if the field is unset or not a string.
+fn debug_field(config: &prost_build::Config, field: &str) -> Option<String> {
+    let debug = format!("{config:?}");
+    let start = debug.find(&format!("{field}: "))? + field.len() + 2;
+    let rest = debug[start..]
+        .strip_prefix("Some(")
+        .unwrap_or(&debug[start..]);
+    let mut chars = rest.strip_prefix('"')?.chars();
+    let mut value = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                '0' => value.push('\0'),
+                'u' => {
+                    let code = chars
+                        .by_ref()
+                        .skip(1)
+                        .take_while(|c| *c != '}')
+                        .collect::<String>();
+                    value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    };
+
+    use super::*;
+
+    fn message(name: &str, fields: Vec<FieldDescriptorProto>) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_string()),
+            field: fields,
+            ..Default::default()
+        }
+    }
+
+    fn message_field(name: &str, type_name: &str) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(1),
+            label: Some(Label::Optional as i32),
+            r#type: Some(Type::Message as i32),
+            type_name: Some(type_name.to_string()),
+            json_name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn file(
+        name: &str,
+        package: &str,
+        deps: &[&str],
+        messages: Vec<DescriptorProto>,
+    ) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_string()),
+            package: Some(package.to_string()),
+            dependency: deps.iter().map(|dep| dep.to_string()).collect(),
+            message_type: messages,
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn skip_imported_and_extern_types() {
+        let out = std::env::temp_dir().join(format!("prost-wkt-builder-{}", std::process::id()));
+        fs::create_dir_all(&out).unwrap();
+
+        let descriptor = FileDescriptorSet {
+            file: vec![
+                file(
+                    "google/protobuf/timestamp.proto",
+                    "google.protobuf",
+                    &[],
+                    vec![message("Timestamp", Vec::new())],
+                ),
+                file(
+                    "other/shared.proto",
+                    "other",
+                    &[],
+                    vec![message("Shared", Vec::new())],
+                ),
+                file(
+                    "my/event.proto",
+                    "my.pkg",
+                    &["google/protobuf/timestamp.proto", "other/shared.proto"],
+                    vec![message(
+                        "Event",
+                        vec![
+                            message_field("at", ".google.protobuf.Timestamp"),
+                            FieldDescriptorProto {
+                                number: Some(2),
+                                ..message_field("shared", ".other.Shared")
+                            },
+                        ],
+                    )],
+                ),
+            ],
+        };
+        Builder::new()
+            .out_dir(&out)
+            .extern_path(".other", "::other")
+            .compile_fds(descriptor)
+            .unwrap();
+
+        let serde_code = fs::read_to_string(out.join("my.pkg.serde.rs")).unwrap();
+        assert!(serde_code.contains("impl :: prost :: Name for Event"));
+        assert!(!out.join("google.protobuf.serde.rs").exists());
+        assert!(!out.join("other.serde.rs").exists());
+
+        fs::remove_dir_all(out).unwrap();
+    }
+
+    #[test]
+    fn follow_config_settings() {
+        let out =
+            std::env::temp_dir().join(format!("prost-wkt-builder-config-{}", std::process::id()));
+        fs::create_dir_all(&out).unwrap();
+
+        let mut config = prost_build::Config::new();
+        config.default_package_filename("packageless");
+        let descriptor = FileDescriptorSet {
+            file: vec![
+                file(
+                    "google/protobuf/timestamp.proto",
+                    "google.protobuf",
+                    &[],
+                    vec![message("Timestamp", Vec::new())],
+                ),
+                FileDescriptorProto {
+                    package: None,
+                    ..file(
+                        "event.proto",
+                        "",
+                        &["google/protobuf/timestamp.proto"],
+                        vec![message(
+                            "Event",
+                            vec![message_field("at", ".google.protobuf.Timestamp")],
+                        )],
+                    )
+                },
+            ],
+        };
+        Builder::with_config(config)
+            .out_dir(&out)
+            .serde_options(SerdeOptions::default().with_prost_wkt_types_path("::sdk::wkt"))
+            .compile_fds(descriptor)
+            .unwrap();
+
+        let code = fs::read_to_string(out.join("packageless.rs")).unwrap();
+        assert!(code.contains("::sdk::wkt::Timestamp"));
+        assert!(out.join("packageless.serde.rs").exists());
+
+        fs::remove_dir_all(out).unwrap();
+    }
+
+    #[test]
+    fn derive_prost_type_name_domains() {
+        let out =
+            std::env::temp_dir().join(format!("prost-wkt-builder-names-{}", std::process::id()));
+        fs::create_dir_all(&out).unwrap();
+
+        let descriptor = FileDescriptorSet {
+            file: vec![file(
+                "my/event.proto",
+                "my.pkg",
+                &[],
+                vec![message("Event", Vec::new())],
+            )],
+        };
+        Builder::new()
+            .out_dir(&out)
+            .serde_options(
+                SerdeOptions::default()
+                    .with_prost_type_names(true)
+                    .with_custom_type_url_generator(|package, message| {
+                        format!("example.com/{package}.{message}")
+                    }),
+            )
+            .compile_fds(descriptor.clone())
+            .unwrap();
+
+        let code = fs::read_to_string(out.join("my.pkg.rs")).unwrap();
+        assert!(code.contains("\"example.com/my.pkg.Event\""));
+        let serde_code = fs::read_to_string(out.join("my.pkg.serde.rs")).unwrap();
+        assert!(!serde_code.contains("impl :: prost :: Name for Event"));
+
+        let error = Builder::new()
+            .out_dir(&out)
+            .serde_options(
+                SerdeOptions::default()
+                    .with_prost_type_names(true)
+                    .with_custom_type_url_generator(|_, message| format!("example.com/{message}")),
+            )
+            .compile_fds(descriptor)
+            .unwrap_err();
+        assert!(error.to_string().contains("`example.com/Event`"));
+
+        fs::remove_dir_all(out).unwrap();
+    }
+
+    #[test]
+    fn read_config_fields() {
+        let mut config = prost_build::Config::new();
+        assert_eq!(
+            debug_field(&config, "default_package_filename").as_deref(),
+            Some("_")
+        );
+        assert_eq!(debug_field(&config, "file_descriptor_set_path"), None);
+
+        config
+            .default_package_filename("a \"b\" \\ ü")
+            .file_descriptor_set_path("out/set.bin");
+        assert_eq!(
+            debug_field(&config, "default_package_filename").as_deref(),
+            Some("a \"b\" \\ ü")
+        );
+        assert_eq!(
+            debug_field(&config, "file_descriptor_set_path").as_deref(),
+            Some("out/set.bin")
+        );
+    }
+
+    // A `FileDescriptorProto` with a custom file option, declared as a regular field.
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct CustomFile {
+        #[prost(string, optional, tag = "1")]
+        name: Option<String>,
+        #[prost(string, optional, tag = "2")]
+        package: Option<String>,
+        #[prost(message, repeated, tag = "4")]
+        message_type: Vec<DescriptorProto>,
+        #[prost(message, optional, tag = "8")]
+        options: Option<CustomFileOptions>,
+        #[prost(string, optional, tag = "12")]
+        syntax: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct CustomFileOptions {
+        #[prost(string, optional, tag = "50000")]
+        type_domain: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct CustomFileSet {
+        #[prost(message, repeated, tag = "1")]
+        file: Vec<CustomFile>,
+    }
+
+    #[test]
+    fn generate_type_urls_from_custom_options() {
+        let out =
+            std::env::temp_dir().join(format!("prost-wkt-builder-options-{}", std::process::id()));
+        fs::create_dir_all(&out).unwrap();
+
+        let encoded = CustomFileSet {
+            file: vec![CustomFile {
+                name: Some("my/event.proto".to_string()),
+                package: Some("my.pkg".to_string()),
+                message_type: vec![message("Event", Vec::new())],
+                options: Some(CustomFileOptions {
+                    type_domain: Some("example.com".to_string()),
+                }),
+                syntax: Some("proto3".to_string()),
+            }],
+        }
+        .encode_to_vec();
+        Builder::new()
+            .out_dir(&out)
+            .serde_options(SerdeOptions::default().with_type_url_generator(|context| {
+                let file = CustomFile::decode(context.encoded_file().unwrap()).unwrap();
+                let domain = file.options.unwrap().type_domain.unwrap();
+                format!("{domain}/{}", context.full_name())
+            }))
+            .compile_encoded_fds(&encoded)
+            .unwrap();
+
+        let serde_code = fs::read_to_string(out.join("my.pkg.serde.rs")).unwrap();
+        assert!(serde_code.contains("\"example.com/my.pkg.Event\""));
+
+        fs::remove_dir_all(out).unwrap();
+    }
+}
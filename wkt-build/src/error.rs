@@ -0,0 +1,80 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An error that occurred while generating the serde support for a package.
+///
+/// The error names the package, the message (if the error is specific to one) and the file the
+/// generated code was being written to.
+#[derive(Debug)]
+pub struct Error {
+    package: String,
+    message: Option<String>,
+    path: PathBuf,
+    source: io::Error,
+}
+
+impl Error {
+    pub(crate) fn new(package: &str, path: &Path, source: io::Error) -> Self {
+        Self {
+            package: package.to_string(),
+            message: None,
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub(crate) fn with_message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// The protobuf package the code was generated for. Empty for proto files without a package
+    /// declaration.
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// The name of the message the code was generated for, including its enclosing messages,
+    /// e.g. `Outer.Inner`.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The path of the file the generated code was written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.message, self.package.is_empty()) {
+            (Some(message), true) => write!(f, "failed to generate serde code for `{message}`")?,
+            (Some(message), false) => write!(
+                f,
+                "failed to generate serde code for `{}.{message}`",
+                self.package
+            )?,
+            (None, true) => write!(f, "failed to generate serde code for the default package")?,
+            (None, false) => write!(
+                f,
+                "failed to generate serde code for package `{}`",
+                self.package
+            )?,
+        }
+        write!(f, " in {}: {}", self.path.display(), self.source)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        io::Error::new(error.source.kind(), error)
+    }
+}
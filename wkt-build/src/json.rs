@@ -0,0 +1,591 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+
+use crate::{rust_field_ident, rust_type_path, rust_variant_ident};
+
+// How the scalar values of a field are represented in JSON, see `prost_wkt::json`.
+enum Encoding {
+    Plain,
+    Value,
+    Int32,
+    Int64,
+    Float,
+    Bytes,
+    Enum(Ident),
+    NullValue,
+    Empty,
+}
+
+impl Encoding {
+    fn of(field: &FieldDescriptorProto, enums: &mut EnumMarkers) -> Self {
+        match field.r#type() {
+            Type::Int64 | Type::Uint64 | Type::Sint64 | Type::Fixed64 | Type::Sfixed64 => {
+                Encoding::Int64
+            }
+            Type::Int32 | Type::Uint32 | Type::Sint32 | Type::Fixed32 | Type::Sfixed32 => {
+                Encoding::Int32
+            }
+            Type::Enum if field.type_name() == ".google.protobuf.NullValue" => Encoding::NullValue,
+            Type::Enum => match enums.marker(field.type_name()) {
+                Some(marker) => Encoding::Enum(marker),
+                None => Encoding::Int32,
+            },
+            Type::Float | Type::Double => Encoding::Float,
+            Type::Bytes => Encoding::Bytes,
+            Type::Message => match field.type_name() {
+                ".google.protobuf.Value" if field.label() != Label::Repeated => Encoding::Value,
+                // prost maps the wrapper types to an `Option` of the wrapped scalar and `Empty` to
+                // `()`, so they are encoded like the scalar fields.
+                ".google.protobuf.Int64Value" | ".google.protobuf.UInt64Value" => Encoding::Int64,
+                ".google.protobuf.Int32Value" | ".google.protobuf.UInt32Value" => Encoding::Int32,
+                ".google.protobuf.FloatValue" | ".google.protobuf.DoubleValue" => Encoding::Float,
+                ".google.protobuf.BytesValue" => Encoding::Bytes,
+                ".google.protobuf.Empty" => Encoding::Empty,
+                _ => Encoding::Plain,
+            },
+            _ => Encoding::Plain,
+        }
+    }
+
+    // The marker type in `prost_wkt::json`, where `json` is the path of that module.
+    fn marker(&self, json: &TokenStream) -> Option<TokenStream> {
+        match self {
+            Encoding::Plain | Encoding::Value => None,
+            Encoding::Int32 => Some(quote! { #json::Int32 }),
+            Encoding::Int64 => Some(quote! { #json::Int64 }),
+            Encoding::Float => Some(quote! { #json::Float }),
+            Encoding::Bytes => Some(quote! { #json::Bytes }),
+            Encoding::Enum(marker) => Some(quote! { #json::Enum<#marker> }),
+            Encoding::NullValue => Some(quote! { #json::NullValue }),
+            Encoding::Empty => Some(quote! { #json::Empty }),
+        }
+    }
+}
+
+/// All enums of the file descriptor set by their fully qualified name, e.g. `.my.pkg.Status`.
+pub(crate) struct Enums<'a> {
+    enums: HashMap<String, &'a EnumDescriptorProto>,
+}
+
+impl<'a> Enums<'a> {
+    pub(crate) fn new(descriptor: &'a FileDescriptorSet) -> Self {
+        fn add_nested<'a>(
+            enums: &mut HashMap<String, &'a EnumDescriptorProto>,
+            prefix: &str,
+            msg: &'a DescriptorProto,
+        ) {
+            let prefix = format!("{prefix}.{}", msg.name());
+            for enum_type in &msg.enum_type {
+                enums.insert(format!("{prefix}.{}", enum_type.name()), enum_type);
+            }
+            for nested in &msg.nested_type {
+                add_nested(enums, &prefix, nested);
+            }
+        }
+
+        let mut enums = HashMap::new();
+        for fd in &descriptor.file {
+            let prefix = match fd.package() {
+                "" => String::new(),
+                package => format!(".{package}"),
+            };
+            for enum_type in &fd.enum_type {
+                enums.insert(format!("{prefix}.{}", enum_type.name()), enum_type);
+            }
+            for msg in &fd.message_type {
+                add_nested(&mut enums, &prefix, msg);
+            }
+        }
+        Enums { enums }
+    }
+}
+
+// The enums used by the fields of a message. Each gets a local marker type implementing
+// `prost_wkt::json::EnumValues` with the values from the enum descriptor, so that the enum does
+// not have to be resolved to its Rust path.
+struct EnumMarkers<'a> {
+    enums: &'a Enums<'a>,
+    json: TokenStream,
+    used: Vec<(String, Ident)>,
+}
+
+impl EnumMarkers<'_> {
+    fn marker(&mut self, type_name: &str) -> Option<Ident> {
+        if let Some((_, marker)) = self.used.iter().find(|(name, _)| name == type_name) {
+            return Some(marker.clone());
+        }
+        self.enums.enums.get(type_name)?;
+        let marker = format_ident!("Enum{}", self.used.len());
+        self.used.push((type_name.to_string(), marker.clone()));
+        Some(marker)
+    }
+
+    fn gen_markers(&self) -> TokenStream {
+        let json = &self.json;
+        let markers = self.used.iter().map(|(type_name, marker)| {
+            let values = self.enums.enums[type_name].value.iter().map(|value| {
+                let name = value.name();
+                let number = value.number();
+                quote! { (#name, #number) }
+            });
+            quote! {
+                struct #marker;
+
+                impl #json::EnumValues for #marker {
+                    const VALUES: &'static [(&'static str, i32)] = &[#(#values),*];
+                }
+            }
+        });
+        quote! { #(#markers)* }
+    }
+}
+
+// A regular field of the message, or a member of a oneof in which case the identifier is that of
+// the oneof enum variant.
+struct Field {
+    json: TokenStream,
+    ident: TokenStream,
+    json_name: String,
+    proto_name: String,
+    encoding: Encoding,
+}
+
+impl Field {
+    fn new(
+        msg: &DescriptorProto,
+        field: &FieldDescriptorProto,
+        ident: TokenStream,
+        enums: &mut EnumMarkers,
+    ) -> Self {
+        let encoding = match map_value(msg, field) {
+            Some(value) => Encoding::of(value, enums),
+            None => Encoding::of(field, enums),
+        };
+        let encoding = match encoding {
+            // `Value` is only special when it is the field type itself.
+            Encoding::Value if field.label() == Label::Repeated => Encoding::Plain,
+            encoding => encoding,
+        };
+        Field {
+            json: enums.json.clone(),
+            ident,
+            json_name: field
+                .json_name
+                .clone()
+                .unwrap_or_else(|| to_json_name(field.name())),
+            proto_name: field.name().to_string(),
+            encoding,
+        }
+    }
+
+    // The names accepted when deserializing.
+    fn names(&self) -> TokenStream {
+        let json_name = &self.json_name;
+        let proto_name = &self.proto_name;
+        if json_name == proto_name {
+            quote! { #json_name }
+        } else {
+            quote! { #json_name | #proto_name }
+        }
+    }
+
+    fn serialize_value(&self, value: TokenStream) -> TokenStream {
+        let json = &self.json;
+        match self.encoding.marker(json) {
+            Some(marker) => quote! { &#json::SerializeAs::<#marker, _>::new(#value) },
+            None => value,
+        }
+    }
+
+    fn deserialize_value(&self) -> TokenStream {
+        let json = &self.json;
+        match (&self.encoding, self.encoding.marker(json)) {
+            (_, Some(marker)) => quote! {
+                map.next_value::<#json::DeserializeAs<#marker, _>>()?.into_inner()
+            },
+            (Encoding::Value, _) => quote! {
+                ::std::option::Option::Some(map.next_value()?)
+            },
+            _ => quote! {
+                map.next_value::<#json::OrDefault<_>>()?.into_inner()
+            },
+        }
+    }
+
+    // Deserializes a oneof member, where `null` means the member is not set.
+    fn deserialize_optional_value(&self) -> TokenStream {
+        let json = &self.json;
+        match (&self.encoding, self.encoding.marker(json)) {
+            (_, Some(marker)) => quote! {
+                map.next_value::<::std::option::Option<#json::DeserializeAs<#marker, _>>>()?
+                    .map(#json::DeserializeAs::into_inner)
+            },
+            (Encoding::Value, _) => quote! {
+                ::std::option::Option::Some(map.next_value()?)
+            },
+            _ => quote! {
+                map.next_value::<::std::option::Option<_>>()?
+            },
+        }
+    }
+}
+
+struct Oneof {
+    ident: TokenStream,
+    type_name: TokenStream,
+    name: String,
+    variants: Vec<Field>,
+}
+
+impl Oneof {
+    // Match arms that serialize the set member as an entry of `state`, which is either a struct or
+    // a map serializer.
+    fn serialize_arms(&self, serialize: TokenStream) -> TokenStream {
+        let type_name = &self.type_name;
+        let arms = self.variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let json_name = &variant.json_name;
+            let value = variant.serialize_value(quote! { value });
+            quote! {
+                #type_name::#ident(value) => {
+                    state.#serialize(#json_name, #value)?;
+                }
+            }
+        });
+        quote! { #(#arms)* }
+    }
+
+    // Match arms that deserialize a member into `target`, rejecting a second member of the oneof.
+    fn deserialize_arms(&self, target: TokenStream) -> TokenStream {
+        let type_name = &self.type_name;
+        let duplicate = format!("more than one field of oneof `{}` is set", self.name);
+        let arms = self.variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let names = variant.names();
+            let value = variant.deserialize_optional_value();
+            quote! {
+                #names => {
+                    if let ::std::option::Option::Some(value) = #value {
+                        if #target.is_some() {
+                            return ::std::result::Result::Err(serde::de::Error::custom(#duplicate));
+                        }
+                        #target = ::std::option::Option::Some(#type_name::#ident(value));
+                    }
+                }
+            }
+        });
+        quote! { #(#arms)* }
+    }
+}
+
+/// Generates the proto3 JSON `Serialize` and `Deserialize` implementations for a message.
+///
+/// Field names are taken from the `json_name` of the descriptor (the proto field name is accepted
+/// as well when deserializing), fields with default values are omitted, and 64-bit integers,
+/// floats and bytes use their proto3 JSON representation. The members of a oneof are flattened
+/// into the message.
+pub(crate) fn gen_json_impls(
+    rust_file: &mut impl Write,
+    message_path: &[&str],
+    msg: &DescriptorProto,
+    enums: &Enums,
+    prost_wkt: &TokenStream,
+) -> io::Result<()> {
+    let type_name = rust_type_path(message_path);
+    let message_name = message_path.join(".");
+    let expecting = format!("a {message_name} message");
+    let json = quote! { #prost_wkt::json };
+
+    let mut enums = EnumMarkers {
+        enums,
+        json: json.clone(),
+        used: Vec::new(),
+    };
+    let (fields, oneofs) = json_members(message_path, msg, &mut enums);
+    let enum_markers = enums.gen_markers();
+
+    let present = fields
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            quote! { !#json::is_default(&self.#ident) }
+        })
+        .chain(oneofs.iter().map(|oneof| {
+            let ident = &oneof.ident;
+            quote! { self.#ident.is_some() }
+        }));
+    let count = fields.len() + oneofs.len();
+    let present = quote! { [#(#present),*] };
+    let serialize_fields = fields.iter().enumerate().map(|(idx, field)| {
+        let ident = &field.ident;
+        let json_name = &field.json_name;
+        let value = field.serialize_value(quote! { &self.#ident });
+        quote! {
+            if present[#idx] {
+                state.serialize_field(#json_name, #value)?;
+            }
+        }
+    });
+    let serialize_oneofs = oneofs.iter().map(|oneof| {
+        let ident = &oneof.ident;
+        let arms = oneof.serialize_arms(quote! { serialize_field });
+        quote! {
+            if let ::std::option::Option::Some(value) = &self.#ident {
+                match value {
+                    #arms
+                }
+            }
+        }
+    });
+
+    let deserialize_fields = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let names = field.names();
+        let value = field.deserialize_value();
+        quote! {
+            #names => {
+                message.#ident = #value;
+            }
+        }
+    });
+    let deserialize_oneofs = oneofs.iter().map(|oneof| {
+        let ident = &oneof.ident;
+        oneof.deserialize_arms(quote! { message.#ident })
+    });
+
+    let oneof_impls = oneofs.iter().map(gen_oneof_impls);
+
+    let tokens = quote! {
+        #[allow(
+            dead_code,
+            unused_mut,
+            clippy::match_single_binding,
+            clippy::possible_missing_else
+        )]
+        const _: () = {
+            use #prost_wkt::serde;
+
+            #enum_markers
+
+            impl serde::Serialize for #type_name {
+                fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    use serde::ser::SerializeStruct;
+
+                    let present: [bool; #count] = #present;
+                    let len = present.iter().filter(|present| **present).count();
+                    let mut state = serializer.serialize_struct(#message_name, len)?;
+                    #(#serialize_fields)*
+                    #(#serialize_oneofs)*
+                    state.end()
+                }
+            }
+
+            impl<'de> serde::Deserialize<'de> for #type_name {
+                fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    struct MessageVisitor;
+
+                    impl<'de> serde::de::Visitor<'de> for MessageVisitor {
+                        type Value = #type_name;
+
+                        fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                            formatter.write_str(#expecting)
+                        }
+
+                        fn visit_map<A>(self, mut map: A) -> ::std::result::Result<Self::Value, A::Error>
+                        where
+                            A: serde::de::MapAccess<'de>,
+                        {
+                            let mut message = <#type_name as ::std::default::Default>::default();
+                            while let ::std::option::Option::Some(key) = map.next_key::<::std::string::String>()? {
+                                match key.as_str() {
+                                    #(#deserialize_fields)*
+                                    #(#deserialize_oneofs)*
+                                    _ => {
+                                        map.next_value::<serde::de::IgnoredAny>()?;
+                                    }
+                                }
+                            }
+                            ::std::result::Result::Ok(message)
+                        }
+                    }
+
+                    deserializer.deserialize_map(MessageVisitor)
+                }
+            }
+
+            #(#oneof_impls)*
+        };
+    };
+
+    writeln!(rust_file)?;
+    writeln!(rust_file, "{}", &tokens)
+}
+
+// Generates the implementations for a oneof enum on its own, which (de)serializes as an object
+// holding just the set member.
+fn gen_oneof_impls(oneof: &Oneof) -> TokenStream {
+    let type_name = &oneof.type_name;
+    let expecting = format!("an object with one field of oneof `{}`", oneof.name);
+    let missing = format!("no field of oneof `{}` is set", oneof.name);
+    let serialize_arms = oneof.serialize_arms(quote! { serialize_entry });
+    let deserialize_arms = oneof.deserialize_arms(quote! { oneof });
+
+    quote! {
+        impl serde::Serialize for #type_name {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                use serde::ser::SerializeMap;
+
+                let mut state = serializer.serialize_map(::std::option::Option::Some(1))?;
+                match self {
+                    #serialize_arms
+                }
+                state.end()
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for #type_name {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct OneofVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for OneofVisitor {
+                    type Value = #type_name;
+
+                    fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                        formatter.write_str(#expecting)
+                    }
+
+                    fn visit_map<A>(self, mut map: A) -> ::std::result::Result<Self::Value, A::Error>
+                    where
+                        A: serde::de::MapAccess<'de>,
+                    {
+                        let mut oneof = ::std::option::Option::None;
+                        while let ::std::option::Option::Some(key) = map.next_key::<::std::string::String>()? {
+                            match key.as_str() {
+                                #deserialize_arms
+                                _ => {
+                                    map.next_value::<serde::de::IgnoredAny>()?;
+                                }
+                            }
+                        }
+                        oneof.ok_or_else(|| serde::de::Error::custom(#missing))
+                    }
+                }
+
+                deserializer.deserialize_map(OneofVisitor)
+            }
+        }
+    }
+}
+
+fn json_members(
+    message_path: &[&str],
+    msg: &DescriptorProto,
+    enums: &mut EnumMarkers,
+) -> (Vec<Field>, Vec<Oneof>) {
+    // Members of a real oneof are (de)serialized with the oneof. Proto3 optional fields live in a
+    // synthetic oneof but are generated by prost as regular fields.
+    let fields = msg
+        .field
+        .iter()
+        .filter(|field| field.oneof_index.is_none() || field.proto3_optional())
+        .map(|field| Field::new(msg, field, rust_field_ident(field.name()), enums))
+        .collect();
+
+    let oneofs = msg
+        .oneof_decl
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, oneof)| {
+            let variants: Vec<Field> = msg
+                .field
+                .iter()
+                .filter(|field| field.oneof_index == Some(idx as i32) && !field.proto3_optional())
+                .map(|field| Field::new(msg, field, rust_variant_ident(field.name()), enums))
+                .collect();
+            if variants.is_empty() {
+                return None;
+            }
+            let mut oneof_path = message_path.to_vec();
+            oneof_path.push(oneof.name());
+            Some(Oneof {
+                ident: rust_field_ident(oneof.name()),
+                type_name: rust_type_path(&oneof_path),
+                name: oneof.name().to_string(),
+                variants,
+            })
+        })
+        .collect();
+
+    (fields, oneofs)
+}
+
+// Returns the value field of the map entry if the field is a map.
+fn map_value<'a>(
+    msg: &'a DescriptorProto,
+    field: &FieldDescriptorProto,
+) -> Option<&'a FieldDescriptorProto> {
+    if field.label() != Label::Repeated || field.r#type() != Type::Message {
+        return None;
+    }
+    let entry_name = field.type_name().rsplit('.').next()?;
+    msg.nested_type
+        .iter()
+        .find(|nested| {
+            nested.name() == entry_name
+                && nested
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.map_entry)
+                    .unwrap_or(false)
+        })
+        .and_then(|entry| entry.field.iter().find(|field| field.number() == 2))
+}
+
+// Same conversion as protoc uses to fill in `json_name`: underscores are dropped and the letter
+// following an underscore is capitalized.
+pub(crate) fn to_json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_name_conversion() {
+        assert_eq!(to_json_name("foo_bar"), "fooBar");
+        assert_eq!(to_json_name("foo_bar_2"), "fooBar2");
+        assert_eq!(to_json_name("requestId"), "requestId");
+        assert_eq!(to_json_name("_private"), "Private");
+    }
+}
@@ -1,66 +1,470 @@
-use heck::ToUpperCamelCase;
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 
 pub use prost::Message;
+pub use prost_build;
 pub use prost_types::FileDescriptorSet;
 
 use prost_build::Module;
+use prost_types::{DescriptorProto, FileDescriptorProto};
+
+mod builder;
+pub use crate::builder::*;
+
+mod error;
+pub use crate::error::Error;
+
+mod type_url;
+pub use crate::type_url::TypeUrlContext;
+
+mod json;
+
+type TypeUrlGenerator = Box<dyn Fn(&TypeUrlContext<'_>) -> String + 'static>;
 
 pub struct SerdeOptions {
-    type_url_generator: Box<dyn Fn(&str, &str) -> String + 'static>,
+    type_url_generator: TypeUrlGenerator,
+    default_package_filename: Option<String>,
+    proto3_json: bool,
+    include_paths: Vec<String>,
+    exclude_paths: Vec<String>,
+    prost_type_names: bool,
+    prost_wkt_path: TokenStream,
+    prost_path: TokenStream,
+    prost_wkt_types_path: String,
+    file_descriptor_set: bool,
+    static_registry: bool,
 }
 
+/// Adds the serde support to the code prost generated in `out`.
+///
+/// # Panics
+///
+/// Panics if the generated code cannot be written, see [`try_add_serde`] for a non-panicking
+/// version.
 pub fn add_serde(out: PathBuf, descriptor: FileDescriptorSet) {
     add_serde_with_options(out, descriptor, SerdeOptions::default())
 }
 
+/// Adds the serde support to the code prost generated in `out`, using the given options.
+///
+/// # Panics
+///
+/// Panics if the generated code cannot be written, see [`try_add_serde_with_options`] for a
+/// non-panicking version.
 pub fn add_serde_with_options(out: PathBuf, descriptor: FileDescriptorSet, options: SerdeOptions) {
-    for fd in &descriptor.file {
-        let package_name = match fd.package {
-            Some(ref pkg) => pkg,
-            None => continue,
-        };
+    if let Err(error) = try_add_serde_with_options(out, descriptor, options) {
+        panic!("{error}");
+    }
+}
+
+/// Adds the serde support to the code prost generated in `out`, returning an error if the
+/// generated code cannot be written.
+pub fn try_add_serde(out: PathBuf, descriptor: FileDescriptorSet) -> Result<(), Error> {
+    try_add_serde_with_options(out, descriptor, SerdeOptions::default())
+}
 
-        let rust_path = out
-            .join(Module::from_protobuf_package_name(package_name).to_file_name_or(package_name));
+/// Adds the serde support to the code prost generated in `out` using the given options,
+/// returning an error if the generated code cannot be written.
+pub fn try_add_serde_with_options(
+    out: PathBuf,
+    descriptor: FileDescriptorSet,
+    options: SerdeOptions,
+) -> Result<(), Error> {
+    let default_package_filename = options.default_package_filename.as_deref().unwrap_or("_");
+    gen_serde(
+        &out,
+        &descriptor,
+        None,
+        &options,
+        default_package_filename,
+        &[],
+    )
+}
 
-        // In some cases the generated file would be in empty. These files are no longer created by Prost, so
-        // we'll create here. Otherwise we append.
-        let mut rust_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(rust_path)
-            .unwrap();
+// Generates the serde code for the files of the descriptor set. The `encoded` set, if known, is
+// passed on to the type url generator for the custom options. Files without a package are written
+// to `default_package_filename`, like prost does. Messages covered by one of the `extern_paths` are
+// skipped, as prost does not generate them either, and so are files that only contain such
+// messages.
+fn gen_serde(
+    out: &Path,
+    descriptor: &FileDescriptorSet,
+    encoded: Option<&[u8]>,
+    options: &SerdeOptions,
+    default_package_filename: &str,
+    extern_paths: &[String],
+) -> Result<(), Error> {
+    let enums = json::Enums::new(descriptor);
+    let encoded_files = encoded
+        .and_then(type_url::split_files)
+        .filter(|files| files.len() == descriptor.file.len())
+        .unwrap_or_default();
+    let is_extern = |fq_path: &str| extern_paths.iter().any(|path| path_matches(path, fq_path));
 
-        for msg in &fd.message_type {
-            let message_name = match msg.name {
-                Some(ref name) => name,
-                None => continue,
-            };
+    // Several proto files can share a package, so the code is collected per package file first.
+    let mut packages: BTreeMap<String, PackageCode> = BTreeMap::new();
+    for (index, fd) in descriptor.file.iter().enumerate() {
+        let encoded_file = encoded_files.get(index).copied();
+        // Files without a package declaration are written by prost to the default package file.
+        let package_name = fd.package.as_deref().unwrap_or_default();
+        let type_paths = fd
+            .message_type
+            .iter()
+            .map(DescriptorProto::name)
+            .chain(fd.enum_type.iter().map(|e| e.name()))
+            .map(|name| match package_name {
+                "" => format!(".{name}"),
+                package => format!(".{package}.{name}"),
+            })
+            .collect::<Vec<_>>();
+        if !type_paths.is_empty() && type_paths.iter().all(|path| is_extern(path)) {
+            continue;
+        }
 
-            let type_url = (options.type_url_generator)(package_name, message_name);
+        let file_name = Module::from_protobuf_package_name(package_name)
+            .to_file_name_or(default_package_filename);
+        let serde_path = out.join(serde_file_name(&file_name));
+        let package = packages.entry(file_name).or_insert_with(|| PackageCode {
+            package_name,
+            files: Vec::new(),
+            code: Vec::new(),
+            entries: Vec::new(),
+        });
+        package.files.push(fd);
 
-            gen_trait_impl(&mut rust_file, package_name, message_name, &type_url);
+        for (msg, path) in fd.message_type.iter().zip(&type_paths) {
+            if is_extern(path) {
+                continue;
+            }
+            gen_message_impls(package, fd, encoded_file, &[], msg, options, &enums).map_err(
+                |(message, error)| {
+                    Error::new(package_name, &serde_path, error).with_message(&message)
+                },
+            )?;
         }
     }
+
+    for (file_name, package) in packages {
+        let package_name = package.package_name;
+        let serde_file_name = serde_file_name(&file_name);
+        let serde_path = out.join(&serde_file_name);
+
+        let mut content = b"// This file is @generated by prost-wkt-build.\n".to_vec();
+        if options.file_descriptor_set {
+            let tokens = gen_file_descriptor_set(&package.files, options);
+            writeln!(content, "{}", &tokens)
+                .map_err(|error| Error::new(package_name, &serde_path, error))?;
+        }
+        if options.static_registry {
+            let tokens = gen_registry_entries(&package.entries, options);
+            writeln!(content, "{}", &tokens)
+                .map_err(|error| Error::new(package_name, &serde_path, error))?;
+        }
+        content.extend(package.code);
+        write_file_if_changed(&serde_path, &content)
+            .map_err(|error| Error::new(package_name, &serde_path, error))?;
+
+        let rust_path = out.join(&file_name);
+        include_serde_file(&rust_path, &serde_file_name)
+            .map_err(|error| Error::new(package_name, &rust_path, error))?;
+    }
+    Ok(())
+}
+
+// The generated code of a package, collected over all proto files of the package.
+struct PackageCode<'a> {
+    package_name: &'a str,
+    files: Vec<&'a FileDescriptorProto>,
+    code: Vec<u8>,
+    // The `MessageSerdeDecoderEntry` of every message, for the static registry table.
+    entries: Vec<TokenStream>,
+}
+
+// Lists the entries of all messages of the package in a `MESSAGE_SERDE_ENTRIES` table that can be
+// added to a `prost_wkt::registry::Registry`.
+fn gen_registry_entries(entries: &[TokenStream], options: &SerdeOptions) -> TokenStream {
+    let prost_wkt = &options.prost_wkt_path;
+    quote! {
+        /// The `MessageSerde` entries of all messages of this package, to be added to a
+        /// `prost_wkt::registry::Registry`.
+        pub static MESSAGE_SERDE_ENTRIES: &[#prost_wkt::MessageSerdeDecoderEntry] = &[#(#entries),*];
+    }
+}
+
+// Embeds the given proto files of a package as a `FILE_DESCRIPTOR_SET` static in the package
+// module. Their dependencies are left out, as they are embedded in their own packages.
+fn gen_file_descriptor_set(files: &[&FileDescriptorProto], options: &SerdeOptions) -> TokenStream {
+    let embedded = FileDescriptorSet {
+        file: files.iter().map(|fd| (*fd).clone()).collect(),
+    };
+    let encoded = Literal::byte_string(&embedded.encode_to_vec());
+    let prost_wkt = &options.prost_wkt_path;
+
+    quote! {
+        /// The encoded `FileDescriptorSet` of the proto files of this package, without their
+        /// dependencies.
+        pub static FILE_DESCRIPTOR_SET: #prost_wkt::descriptor::EmbeddedFileDescriptorSet =
+            #prost_wkt::descriptor::EmbeddedFileDescriptorSet::new(#encoded);
+    }
+}
+
+// Returns the name of the file holding the generated impls for the given prost file, e.g.
+// `my.pkg.serde.rs` for `my.pkg.rs`.
+fn serde_file_name(file_name: &str) -> String {
+    match file_name.strip_suffix(".rs") {
+        Some(stem) => format!("{stem}.serde.rs"),
+        None => format!("{file_name}.serde.rs"),
+    }
+}
+
+fn write_file_if_changed(path: &Path, content: &[u8]) -> io::Result<()> {
+    if fs::read(path).is_ok_and(|previous| previous == content) {
+        return Ok(());
+    }
+    fs::write(path, content)
+}
+
+// Adds an `include!` of the serde file to the prost generated file, unless it is already there
+// because the prost file was not regenerated since the last run. The prost file does not exist if
+// prost had nothing to generate for the package, in which case it is created.
+fn include_serde_file(rust_path: &Path, serde_file_name: &str) -> io::Result<()> {
+    let include = format!("include!(\"{serde_file_name}\");");
+    let mut content = match fs::read_to_string(rust_path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error),
+    };
+    if content.lines().any(|line| line == include) {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&include);
+    content.push('\n');
+    fs::write(rust_path, content)
+}
+
+// Generates the impls for the given message and then recurses into its nested messages. The
+// `parents` are the names of the enclosing messages, outermost first, so that `Outer.Inner` is
+// registered as `pkg.Outer.Inner` and implemented for `outer::Inner`. Errors are returned along
+// with the name of the message they occurred for.
+fn gen_message_impls(
+    package: &mut PackageCode,
+    fd: &FileDescriptorProto,
+    encoded_file: Option<&[u8]>,
+    parents: &[&str],
+    msg: &DescriptorProto,
+    options: &SerdeOptions,
+    enums: &json::Enums,
+) -> Result<(), (String, io::Error)> {
+    let message_name = match msg.name {
+        Some(ref name) => name.as_str(),
+        None => return Ok(()),
+    };
+
+    // Map fields are backed by synthetic `*Entry` messages for which prost does not generate a type.
+    if msg
+        .options
+        .as_ref()
+        .and_then(|options| options.map_entry)
+        .unwrap_or(false)
+    {
+        return Ok(());
+    }
+
+    let package_name = fd.package();
+    let mut message_path = parents.to_vec();
+    message_path.push(message_name);
+
+    if options.is_selected(package_name, &message_path) {
+        let context = TypeUrlContext::new(fd, encoded_file, msg, &message_path);
+        let type_url = (options.type_url_generator)(&context);
+
+        let entry = gen_trait_impl(&mut package.code, fd, &message_path, &type_url, options)
+            .map_err(|error| (message_path.join("."), error))?;
+        package.entries.push(entry);
+    }
+
+    if options.proto3_json {
+        json::gen_json_impls(
+            &mut package.code,
+            &message_path,
+            msg,
+            enums,
+            &options.prost_wkt_path,
+        )
+        .map_err(|error| (message_path.join("."), error))?;
+    }
+
+    for nested in &msg.nested_type {
+        gen_message_impls(
+            package,
+            fd,
+            encoded_file,
+            &message_path,
+            nested,
+            options,
+            enums,
+        )?;
+    }
+    Ok(())
+}
+
+// Returns the fully qualified path and the type url of every selected message of the descriptor
+// set that is not covered by one of the `extern_paths`, e.g. `(".my.pkg.Outer.Inner", url)`.
+fn selected_type_urls(
+    descriptor: &FileDescriptorSet,
+    encoded: Option<&[u8]>,
+    options: &SerdeOptions,
+    extern_paths: &[String],
+) -> Vec<(String, String)> {
+    fn collect(
+        type_urls: &mut Vec<(String, String)>,
+        fd: &FileDescriptorProto,
+        encoded_file: Option<&[u8]>,
+        parents: &[&str],
+        msg: &DescriptorProto,
+        options: &SerdeOptions,
+        extern_paths: &[String],
+    ) {
+        let mut message_path = parents.to_vec();
+        message_path.push(msg.name());
+        let fq_path = match fd.package() {
+            "" => format!(".{}", message_path.join(".")),
+            package => format!(".{package}.{}", message_path.join(".")),
+        };
+        let is_map_entry = msg
+            .options
+            .as_ref()
+            .and_then(|options| options.map_entry)
+            .unwrap_or(false);
+        if is_map_entry || extern_paths.iter().any(|path| path_matches(path, &fq_path)) {
+            return;
+        }
+        if options.is_selected(fd.package(), &message_path) {
+            let context = TypeUrlContext::new(fd, encoded_file, msg, &message_path);
+            type_urls.push((fq_path, (options.type_url_generator)(&context)));
+        }
+        for nested in &msg.nested_type {
+            collect(
+                type_urls,
+                fd,
+                encoded_file,
+                &message_path,
+                nested,
+                options,
+                extern_paths,
+            );
+        }
+    }
+
+    let encoded_files = encoded
+        .and_then(type_url::split_files)
+        .filter(|files| files.len() == descriptor.file.len())
+        .unwrap_or_default();
+    let mut type_urls = Vec::new();
+    for (index, fd) in descriptor.file.iter().enumerate() {
+        for msg in &fd.message_type {
+            collect(
+                &mut type_urls,
+                fd,
+                encoded_files.get(index).copied(),
+                &[],
+                msg,
+                options,
+                extern_paths,
+            );
+        }
+    }
+    type_urls
 }
 
 // This method uses the `heck` crate (the same that prost uses) to properly format the message name
-// to UpperCamelCase as the prost_build::ident::{to_snake, to_upper_camel} methods
-// in the `ident` module of prost_build is private.
-fn gen_trait_impl(rust_file: &mut File, package_name: &str, message_name: &str, type_url: &str) {
-    let type_name = message_name.to_upper_camel_case();
-    let type_name = format_ident!("{}", type_name);
+// to UpperCamelCase and the enclosing message names to snake_case as the
+// prost_build::ident::{to_snake, to_upper_camel} methods in the `ident` module of prost_build
+// are private. Returns the `MessageSerdeDecoderEntry` of the message.
+fn gen_trait_impl(
+    rust_file: &mut impl Write,
+    fd: &FileDescriptorProto,
+    message_path: &[&str],
+    type_url: &str,
+    options: &SerdeOptions,
+) -> io::Result<TokenStream> {
+    let package_name = fd.package();
+    let message_name = message_path.last().expect("message path is never empty");
+    let type_name = rust_type_path(message_path);
+    let prost_wkt = &options.prost_wkt_path;
+    let prost = &options.prost_path;
+    let full_name = if package_name.is_empty() {
+        message_path.join(".")
+    } else {
+        format!("{}.{}", package_name, message_path.join("."))
+    };
+
+    // With prost's own `Name` impl the type url is still the generated one, which the builder
+    // configured as prost's type name domain.
+    let (type_url_body, name_impl) = if options.prost_type_names {
+        (quote! { #type_url }, quote! {})
+    } else {
+        let name_impl = quote! {
+            impl #prost::Name for #type_name {
+                const PACKAGE: &'static str = #package_name;
+                const NAME: &'static str = #message_name;
+
+                fn full_name() -> String {
+                    #full_name.to_string()
+                }
+
+                fn type_url() -> String {
+                    #type_url.to_string()
+                }
+            }
+        };
+        (quote! { #type_url }, name_impl)
+    };
+
+    let (with_descriptor, descriptor_fn) = if options.file_descriptor_set {
+        let file_name = fd.name();
+        let descriptor = quote! {
+            FILE_DESCRIPTOR_SET.message(#file_name, &[#(#message_path),*])
+        };
+        let descriptor_fn = quote! {
+            fn descriptor(&self) -> ::std::option::Option<#prost_wkt::descriptor::MessageDescriptor> {
+                #descriptor
+            }
+        };
+        (quote! { .with_descriptor(|| #descriptor) }, descriptor_fn)
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    let entry = quote! {
+        #prost_wkt::MessageSerdeDecoderEntry::new(#type_url, |buf: &[u8]| {
+            let msg: #type_name = #prost::Message::decode(buf)?;
+            Ok(::std::boxed::Box::new(msg))
+        })
+        .with_deserializer(|mut value| {
+            if let ::std::option::Option::Some(object) = value.as_object_mut() {
+                object.remove("@type");
+            }
+            let msg: #type_name = #prost_wkt::serde::Deserialize::deserialize(value)?;
+            Ok(::std::boxed::Box::new(msg))
+        })
+        #with_descriptor
+    };
 
     let tokens = quote! {
         #[allow(dead_code)]
         const _: () = {
-            use ::prost_wkt::typetag;
+            use #prost_wkt::typetag;
             #[typetag::serde(name=#type_url)]
-            impl ::prost_wkt::MessageSerde for #type_name {
+            impl #prost_wkt::MessageSerde for #type_name {
                 fn package_name(&self) -> &'static str {
                     #package_name
                 }
@@ -68,52 +472,139 @@ fn gen_trait_impl(rust_file: &mut File, package_name: &str, message_name: &str,
                     #message_name
                 }
                 fn type_url(&self) -> &'static str {
-                    #type_url
+                    #type_url_body
                 }
-                fn new_instance(&self, data: Vec<u8>) -> ::std::result::Result<Box<dyn ::prost_wkt::MessageSerde>, ::prost::DecodeError> {
+                fn new_instance(&self, data: Vec<u8>) -> ::std::result::Result<Box<dyn #prost_wkt::MessageSerde>, #prost::DecodeError> {
                     let mut target = Self::default();
-                    ::prost::Message::merge(&mut target, data.as_slice())?;
-                    let erased: ::std::boxed::Box<dyn ::prost_wkt::MessageSerde> = ::std::boxed::Box::new(target);
+                    #prost::Message::merge(&mut target, data.as_slice())?;
+                    let erased: ::std::boxed::Box<dyn #prost_wkt::MessageSerde> = ::std::boxed::Box::new(target);
                     Ok(erased)
                 }
-                fn try_encoded(&self) -> ::std::result::Result<::std::vec::Vec<u8>, ::prost::EncodeError> {
-                    let mut buf = ::std::vec::Vec::with_capacity(::prost::Message::encoded_len(self));
-                    ::prost::Message::encode(self, &mut buf)?;
+                fn try_encoded(&self) -> ::std::result::Result<::std::vec::Vec<u8>, #prost::EncodeError> {
+                    let mut buf = ::std::vec::Vec::with_capacity(#prost::Message::encoded_len(self));
+                    #prost::Message::encode(self, &mut buf)?;
                     Ok(buf)
                 }
-            }
-
-            ::prost_wkt::inventory::submit!{
-                ::prost_wkt::MessageSerdeDecoderEntry {
-                    type_url: #type_url,
-                    decoder: |buf: &[u8]| {
-                        let msg: #type_name = ::prost::Message::decode(buf)?;
-                        Ok(::std::boxed::Box::new(msg))
-                    }
+                fn clone_box(&self) -> ::std::boxed::Box<dyn #prost_wkt::MessageSerde> {
+                    ::std::boxed::Box::new(::std::clone::Clone::clone(self))
+                }
+                fn eq_dyn(&self, other: &dyn #prost_wkt::MessageSerde) -> bool {
+                    other.downcast_ref::<Self>().is_some_and(|other| self == other)
                 }
+                // The borrow selects the `Debug` implementation of the message if it has one.
+                #[allow(unused_imports, clippy::needless_borrow)]
+                fn fmt_debug(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    use #prost_wkt::{FmtDebug as _, FmtTypeUrl as _};
+                    (&#prost_wkt::DebugMessage(self)).fmt_message(f)
+                }
+                #descriptor_fn
             }
 
-            impl ::prost::Name for #type_name {
-                const PACKAGE: &'static str = #package_name;
-                const NAME: &'static str = #message_name;
-
-                fn type_url() -> String {
-                    #type_url.to_string()
-                }
+            #prost_wkt::inventory::submit!{
+                #entry
             }
+
+            #name_impl
         };
     };
 
-    writeln!(rust_file).unwrap();
-    writeln!(rust_file, "{}", &tokens).unwrap();
+    writeln!(rust_file)?;
+    writeln!(rust_file, "{}", &tokens)?;
+    Ok(entry)
+}
+
+// Returns the path of the prost generated type for the message, relative to the package module,
+// e.g. `outer::Inner` for the nested message `Outer.Inner`.
+pub(crate) fn rust_type_path(message_path: &[&str]) -> TokenStream {
+    let (message_name, parents) = message_path
+        .split_last()
+        .expect("message path is never empty");
+    let modules = parents
+        .iter()
+        .map(|parent| format_ident!("{}", sanitize_identifier(parent.to_snake_case())));
+    let type_name = format_ident!(
+        "{}",
+        sanitize_identifier(message_name.to_upper_camel_case())
+    );
+    quote! { #(#modules::)* #type_name }
+}
+
+// Returns the identifier prost uses for the field (or oneof) with the given proto name.
+pub(crate) fn rust_field_ident(name: &str) -> TokenStream {
+    let ident = format_ident!("{}", sanitize_identifier(name.to_snake_case()));
+    quote! { #ident }
+}
+
+// Returns the identifier prost uses for the oneof enum variant of the field with the given name.
+pub(crate) fn rust_variant_ident(name: &str) -> TokenStream {
+    let ident = format_ident!("{}", sanitize_identifier(name.to_upper_camel_case()));
+    quote! { #ident }
+}
+
+fn parse_path(path: &str) -> TokenStream {
+    path.parse()
+        .unwrap_or_else(|error| panic!("invalid crate path `{path}`: {error}"))
+}
+
+// Matches a fully qualified path like `.my.pkg.Message` the way prost-build matches the paths of
+// `type_attribute`: exactly, by suffix (without a leading dot), by prefix (with a leading dot) or
+// globally with `.`.
+fn path_matches(path: &str, fq_path: &str) -> bool {
+    if path == "." || path == fq_path {
+        return true;
+    }
+    match path.strip_prefix('.') {
+        Some(_) => fq_path
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => fq_path
+            .strip_suffix(path)
+            .is_some_and(|rest| rest.ends_with('.')),
+    }
+}
+
+// Mirrors `prost_build::ident::sanitize_identifier` so that keywords are escaped the same way as in
+// the prost generated code.
+fn sanitize_identifier(ident: String) -> String {
+    match ident.as_str() {
+        // 2015 strict keywords.
+        | "as" | "break" | "const" | "continue" | "else" | "enum" | "false"
+        | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match" | "mod" | "move" | "mut"
+        | "pub" | "ref" | "return" | "static" | "struct" | "trait" | "true"
+        | "type" | "unsafe" | "use" | "where" | "while"
+        // 2018 strict keywords.
+        | "dyn"
+        // 2015 reserved keywords.
+        | "abstract" | "become" | "box" | "do" | "final" | "macro" | "override" | "priv" | "typeof"
+        | "unsized" | "virtual" | "yield"
+        // 2018 reserved keywords.
+        | "async" | "await" | "try"
+        // 2024 reserved keywords.
+        | "gen" => format!("r#{ident}"),
+        // the following keywords are not supported as raw identifiers and are therefore suffixed with an underscore.
+        "_" | "super" | "self" | "Self" | "extern" | "crate" => format!("{ident}_"),
+        // the following keywords begin with a number and are therefore prefixed with an underscore.
+        s if s.starts_with(|c: char| c.is_numeric()) => format!("_{ident}"),
+        _ => ident,
+    }
 }
 
 impl Default for SerdeOptions {
     fn default() -> Self {
         Self {
-            type_url_generator: Box::new(|package, message| {
-                format!("type.googleapis.com/{}.{}", package, message)
+            type_url_generator: Box::new(|context| {
+                format!("type.googleapis.com/{}", context.full_name())
             }),
+            default_package_filename: None,
+            proto3_json: false,
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+            prost_type_names: false,
+            prost_wkt_path: quote! { ::prost_wkt },
+            prost_path: quote! { ::prost },
+            prost_wkt_types_path: "::prost_wkt_types".to_string(),
+            file_descriptor_set: false,
+            static_registry: false,
         }
     }
 }
@@ -122,7 +613,9 @@ impl SerdeOptions {
     /// Set a custom type url generator.
     ///
     /// The generator is a function that takes a package name and a message name and returns a type url.
-    /// I.e by default the type url is will be `type.googleapis.com/{package}.{message}` but you can change it to anything you want according to your needs.
+    /// For nested messages the message name includes the enclosing messages, e.g. `Outer.Inner`.
+    /// For proto files without a package declaration the package name is empty.
+    /// I.e by default the type url is will be `type.googleapis.com/{package}.{message}` (or `type.googleapis.com/{message}` without a package) but you can change it to anything you want according to your needs.
     ///
     /// # Example
     ///
@@ -133,10 +626,310 @@ impl SerdeOptions {
     ///
     ///
     pub fn with_custom_type_url_generator<F: Fn(&str, &str) -> String + 'static>(
+        self,
+        generator: F,
+    ) -> Self {
+        self.with_type_url_generator(move |context| {
+            generator(context.package(), &context.message_path().join("."))
+        })
+    }
+
+    /// Set a custom type url generator that has access to the descriptors of the message.
+    ///
+    /// The generator receives a [`TypeUrlContext`] with the proto file and the message
+    /// descriptors, the path of the message within its enclosing messages and its fully qualified
+    /// name. Use this when the type url depends on more than the package and the message name,
+    /// e.g. on the file the message is declared in.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use prost_wkt_build::SerdeOptions;
+    /// let options = SerdeOptions::default().with_type_url_generator(|context| {
+    ///     match context.file().name() {
+    ///         name if name.starts_with("internal/") => format!("internal.example.com/{}", context.full_name()),
+    ///         _ => format!("type.googleapis.com/{}", context.full_name()),
+    ///     }
+    /// });
+    /// ```
+    pub fn with_type_url_generator<F: Fn(&TypeUrlContext<'_>) -> String + 'static>(
         mut self,
         generator: F,
     ) -> Self {
         self.type_url_generator = Box::new(generator);
         self
     }
+
+    /// Set the file name used for proto files without a package declaration.
+    ///
+    /// This must match the value passed to `prost_build::Config::default_package_filename`, which
+    /// defaults to `_`. The [`Builder`] passes the value on to its config when it is set here, and
+    /// otherwise uses the one configured on the config.
+    pub fn with_default_package_filename<S: Into<String>>(mut self, filename: S) -> Self {
+        self.default_package_filename = Some(filename.into());
+        self
+    }
+
+    /// Generate `Serialize` and `Deserialize` implementations that follow the
+    /// [proto3 JSON mapping](https://protobuf.dev/programming-guides/proto3/#json).
+    ///
+    /// The implementations are generated from the descriptors: fields are named after their
+    /// `json_name` (the proto field name is accepted as well when deserializing), fields holding
+    /// their default value are omitted, 64-bit integers are written as strings, bytes as base64,
+    /// enums as the names of their values and non-finite floats as `"NaN"`, `"Infinity"` or
+    /// `"-Infinity"`. Enums accept numbers as well, and numbers unknown to the enum are preserved.
+    ///
+    /// The members of a oneof are flattened into the message, and deserialization fails if more
+    /// than one member of the same oneof is set.
+    ///
+    /// Do not derive `Serialize` and `Deserialize` on the messages or oneofs yourself when this is
+    /// enabled.
+    pub fn with_proto3_json(mut self, enabled: bool) -> Self {
+        self.proto3_json = enabled;
+        self
+    }
+
+    /// Only generate the `MessageSerde` and `prost::Name` implementations and the decoder
+    /// registration for messages matching the given path. Can be called several times; without
+    /// any include paths all messages are selected.
+    ///
+    /// Paths use the same syntax as `prost_build::Config::type_attribute`: a fully qualified path
+    /// such as `.my.pkg.Message` matches the message exactly, a path without the leading dot such
+    /// as `Message` or `pkg.Message` matches it as a suffix, a package such as `.my.pkg` matches
+    /// everything in it (including nested messages) and `.` matches all messages.
+    ///
+    /// The proto3 JSON implementations enabled by [`with_proto3_json`](Self::with_proto3_json)
+    /// are not affected by the filters.
+    pub fn with_include_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.include_paths.push(path.into());
+        self
+    }
+
+    /// Skip the `MessageSerde` and `prost::Name` implementations and the decoder registration for
+    /// messages matching the given path, e.g. for dependencies that provide their own. Exclude
+    /// paths take precedence over include paths and use the same syntax.
+    pub fn with_exclude_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.exclude_paths.push(path.into());
+        self
+    }
+
+    /// Use the `prost::Name` implementations generated by prost instead of generating them here.
+    ///
+    /// The type url generator is still used for `MessageSerde::type_url` and to register the
+    /// messages for `Any`. The [`Builder`] enables `prost_build::Config::enable_type_names` and
+    /// sets prost's `type_name_domain` of every selected message from the generator, so both
+    /// agree. Compilation fails if a generated type url does not end with the full name of its
+    /// message, as prost's `Name` impl cannot produce such a url.
+    ///
+    /// Without the builder, enable prost's type names yourself, otherwise both emit a `Name` impl
+    /// for every message, and configure `type_name_domain` to produce the same urls, e.g.
+    /// `type.googleapis.com` for the default generator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use prost_wkt_build::{Builder, SerdeOptions};
+    /// let mut builder = Builder::new();
+    /// builder.serde_options(SerdeOptions::default().with_prost_type_names(true));
+    /// ```
+    pub fn with_prost_type_names(mut self, enabled: bool) -> Self {
+        self.prost_type_names = enabled;
+        self
+    }
+
+    /// Set the path of the `prost_wkt` crate used in the generated code. Defaults to
+    /// `::prost_wkt`.
+    ///
+    /// Use this when `prost_wkt` is only available through a re-export, e.g. `::my_sdk::prost_wkt`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path cannot be parsed as Rust tokens.
+    pub fn with_prost_wkt_path<S: AsRef<str>>(mut self, path: S) -> Self {
+        self.prost_wkt_path = parse_path(path.as_ref());
+        self
+    }
+
+    /// Set the path of the `prost` crate used in the generated code, like
+    /// `prost_build::Config::prost_path`. Defaults to `::prost`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path cannot be parsed as Rust tokens.
+    pub fn with_prost_path<S: AsRef<str>>(mut self, path: S) -> Self {
+        self.prost_path = parse_path(path.as_ref());
+        self
+    }
+
+    /// Set the path of the `prost_wkt_types` crate the [`Builder`] maps the well known types to.
+    /// Defaults to `::prost_wkt_types`.
+    ///
+    /// Use this when `prost_wkt_types` is only available through a re-export, e.g.
+    /// `::my_sdk::prost_wkt_types`.
+    pub fn with_prost_wkt_types_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.prost_wkt_types_path = path.into();
+        self
+    }
+
+    /// Embed the file descriptor set in the generated code.
+    ///
+    /// Every package gets a `FILE_DESCRIPTOR_SET` static with the encoded descriptors of its own
+    /// proto files, without their dependencies (see `prost_wkt::descriptor::EmbeddedFileDescriptorSet`), and
+    /// `MessageSerde::descriptor` returns the descriptors of the message and its proto file. This
+    /// is useful to serve gRPC reflection or to inspect messages without shipping the `.proto`
+    /// files.
+    pub fn with_file_descriptor_set(mut self, enabled: bool) -> Self {
+        self.file_descriptor_set = enabled;
+        self
+    }
+
+    /// Generate a `MESSAGE_SERDE_ENTRIES` table per package that lists the `MessageSerde`
+    /// entries of its messages.
+    ///
+    /// The messages are still registered through `inventory` as well. The tables are for targets
+    /// where that does not work: build a `prost_wkt::registry::Registry` from them and install it
+    /// with the `static-registry` feature of `prost-wkt` enabled.
+    pub fn with_static_registry(mut self, enabled: bool) -> Self {
+        self.static_registry = enabled;
+        self
+    }
+
+    fn is_selected(&self, package_name: &str, message_path: &[&str]) -> bool {
+        let fq_path = if package_name.is_empty() {
+            format!(".{}", message_path.join("."))
+        } else {
+            format!(".{}.{}", package_name, message_path.join("."))
+        };
+        let matches = |path: &String| path_matches(path, &fq_path);
+
+        (self.include_paths.is_empty() || self.include_paths.iter().any(matches))
+            && !self.exclude_paths.iter().any(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regenerating_is_idempotent() {
+        let out = std::env::temp_dir().join(format!("prost-wkt-build-{}", std::process::id()));
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("my.pkg.rs"), "pub struct Foo {}\n").unwrap();
+
+        let descriptor = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                package: Some("my.pkg".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Foo".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        add_serde(out.clone(), descriptor.clone());
+        let serde_code = fs::read_to_string(out.join("my.pkg.serde.rs")).unwrap();
+        add_serde(out.clone(), descriptor);
+
+        assert_eq!(
+            fs::read_to_string(out.join("my.pkg.rs")).unwrap(),
+            "pub struct Foo {}\ninclude!(\"my.pkg.serde.rs\");\n"
+        );
+        assert_eq!(
+            fs::read_to_string(out.join("my.pkg.serde.rs")).unwrap(),
+            serde_code
+        );
+        assert_eq!(
+            serde_code.matches("impl :: prost :: Name for Foo").count(),
+            1
+        );
+
+        fs::remove_dir_all(out).unwrap();
+    }
+
+    #[test]
+    fn report_unwritable_output() {
+        let out = std::env::temp_dir()
+            .join(format!("prost-wkt-build-{}", std::process::id()))
+            .join("missing");
+        let descriptor = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                package: Some("my.pkg".to_string()),
+                ..Default::default()
+            }],
+        };
+
+        let error = try_add_serde(out.clone(), descriptor).unwrap_err();
+        assert_eq!(error.package(), "my.pkg");
+        assert_eq!(error.message(), None);
+        assert_eq!(error.path(), out.join("my.pkg.serde.rs"));
+        assert!(error
+            .to_string()
+            .starts_with("failed to generate serde code for package `my.pkg` in "));
+    }
+
+    #[test]
+    fn match_paths_like_prost_build() {
+        assert!(path_matches(".", ".my.pkg.Foo"));
+        assert!(path_matches(".my.pkg.Foo", ".my.pkg.Foo"));
+        assert!(path_matches("Foo", ".my.pkg.Foo"));
+        assert!(path_matches("pkg.Foo", ".my.pkg.Foo"));
+        assert!(path_matches(".my.pkg", ".my.pkg.Foo"));
+        assert!(path_matches(".my.pkg.Foo", ".my.pkg.Foo.Inner"));
+        assert!(path_matches("Foo", ".Foo"));
+        assert!(!path_matches("Foo", ".my.pkg.BigFoo"));
+        assert!(!path_matches(".my.pk", ".my.pkg.Foo"));
+        assert!(!path_matches("pkg", ".my.pkg.Foo"));
+        assert!(!path_matches(".other", ".my.pkg.Foo"));
+    }
+
+    #[test]
+    fn select_messages_with_include_and_exclude_paths() {
+        let options = SerdeOptions::default()
+            .with_include_path(".my.pkg")
+            .with_exclude_path("Foo.Internal");
+        assert!(options.is_selected("my.pkg", &["Foo"]));
+        assert!(options.is_selected("my.pkg", &["Foo", "Inner"]));
+        assert!(!options.is_selected("my.pkg", &["Foo", "Internal"]));
+        assert!(!options.is_selected("google.protobuf", &["Timestamp"]));
+        assert!(!options.is_selected("", &["Foo"]));
+        assert!(SerdeOptions::default().is_selected("", &["Foo"]));
+    }
+
+    #[test]
+    fn generate_type_urls_from_context() {
+        let file = FileDescriptorProto {
+            name: Some("internal/foo.proto".to_string()),
+            package: Some("my.pkg".to_string()),
+            ..Default::default()
+        };
+        let message = DescriptorProto {
+            name: Some("Inner".to_string()),
+            ..Default::default()
+        };
+        let context = TypeUrlContext::new(&file, None, &message, &["Outer", "Inner"]);
+        assert_eq!(context.full_name(), "my.pkg.Outer.Inner");
+
+        let default = SerdeOptions::default();
+        assert_eq!(
+            (default.type_url_generator)(&context),
+            "type.googleapis.com/my.pkg.Outer.Inner"
+        );
+
+        let custom = SerdeOptions::default().with_custom_type_url_generator(|package, message| {
+            format!("custom/{package}/{message}")
+        });
+        assert_eq!(
+            (custom.type_url_generator)(&context),
+            "custom/my.pkg/Outer.Inner"
+        );
+
+        let by_file = SerdeOptions::default().with_type_url_generator(|context| {
+            format!("{}/{}", context.file().name(), context.message().name())
+        });
+        assert_eq!(
+            (by_file.type_url_generator)(&context),
+            "internal/foo.proto/Inner"
+        );
+    }
 }
@@ -0,0 +1,109 @@
+use prost::bytes::Buf;
+use prost::encoding::{decode_key, decode_varint, skip_field, DecodeContext, WireType};
+use prost_types::{DescriptorProto, FileDescriptorProto};
+
+/// The message a type url is generated for, see
+/// [`SerdeOptions::with_type_url_generator`](crate::SerdeOptions::with_type_url_generator).
+pub struct TypeUrlContext<'a> {
+    file: &'a FileDescriptorProto,
+    encoded_file: Option<&'a [u8]>,
+    message: &'a DescriptorProto,
+    message_path: &'a [&'a str],
+    full_name: String,
+}
+
+impl<'a> TypeUrlContext<'a> {
+    pub(crate) fn new(
+        file: &'a FileDescriptorProto,
+        encoded_file: Option<&'a [u8]>,
+        message: &'a DescriptorProto,
+        message_path: &'a [&'a str],
+    ) -> Self {
+        let full_name = match file.package() {
+            "" => message_path.join("."),
+            package => format!("{}.{}", package, message_path.join(".")),
+        };
+        Self {
+            file,
+            encoded_file,
+            message,
+            message_path,
+            full_name,
+        }
+    }
+
+    /// The proto file the message is declared in, including its path and options.
+    ///
+    /// Note that `prost-types` does not keep unknown fields, so custom options (extensions) are
+    /// not available on the descriptor. Read them from [`encoded_file`](Self::encoded_file)
+    /// instead.
+    pub fn file(&self) -> &'a FileDescriptorProto {
+        self.file
+    }
+
+    /// The encoded `FileDescriptorProto` of the file, including the custom options. Decode it with
+    /// a message that declares the options as regular fields:
+    ///
+    /// ```rust
+    /// #[derive(Clone, PartialEq, prost::Message)]
+    /// struct File {
+    ///     #[prost(message, optional, tag = "8")]
+    ///     options: Option<FileOptions>,
+    /// }
+    ///
+    /// #[derive(Clone, PartialEq, prost::Message)]
+    /// struct FileOptions {
+    ///     // extend google.protobuf.FileOptions { string type_domain = 50000; }
+    ///     #[prost(string, optional, tag = "50000")]
+    ///     type_domain: Option<String>,
+    /// }
+    /// ```
+    ///
+    /// Only available when the descriptors were read from their encoded form, as by
+    /// [`Builder::compile_protos`](crate::Builder::compile_protos) and
+    /// [`Builder::compile_encoded_fds`](crate::Builder::compile_encoded_fds).
+    pub fn encoded_file(&self) -> Option<&'a [u8]> {
+        self.encoded_file
+    }
+
+    /// The descriptor of the message.
+    pub fn message(&self) -> &'a DescriptorProto {
+        self.message
+    }
+
+    /// The package of the message. Empty for proto files without a package declaration.
+    pub fn package(&self) -> &'a str {
+        self.file.package()
+    }
+
+    /// The names of the enclosing messages, outermost first, followed by the name of the message
+    /// itself, e.g. `["Outer", "Inner"]`.
+    pub fn message_path(&self) -> &'a [&'a str] {
+        self.message_path
+    }
+
+    /// The fully qualified name of the message without a leading dot, e.g. `my.pkg.Outer.Inner`.
+    pub fn full_name(&self) -> &str {
+        &self.full_name
+    }
+}
+
+// Splits an encoded `FileDescriptorSet` into its encoded files, in order.
+pub(crate) fn split_files(mut encoded: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut files = Vec::new();
+    while encoded.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut encoded).ok()?;
+        if tag == 1 && wire_type == WireType::LengthDelimited {
+            let len = usize::try_from(decode_varint(&mut encoded).ok()?).ok()?;
+            if len > encoded.len() {
+                return None;
+            }
+            let (file, rest) = encoded.split_at(len);
+            files.push(file);
+            encoded = rest;
+        } else {
+            skip_field(wire_type, tag, &mut encoded, DecodeContext::default()).ok()?;
+        }
+    }
+    Some(files)
+}
@@ -0,0 +1,109 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use prost::{DecodeError, EncodeError, Message};
+use prost_wkt::*;
+use prost_wkt_types::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    #[prost(string, tag = "1")]
+    pub name: std::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub timestamp: ::std::option::Option<::prost_wkt_types::Timestamp>,
+    #[prost(string, repeated, tag = "3")]
+    pub tags: ::std::vec::Vec<std::string::String>,
+}
+
+#[typetag::serde(name = "type.googleapis.com/bench.Event")]
+impl prost_wkt::MessageSerde for Event {
+    fn message_name(&self) -> &'static str {
+        "Event"
+    }
+
+    fn package_name(&self) -> &'static str {
+        "bench"
+    }
+
+    fn type_url(&self) -> &'static str {
+        "type.googleapis.com/bench.Event"
+    }
+
+    fn new_instance(&self, data: Vec<u8>) -> Result<Box<dyn MessageSerde>, DecodeError> {
+        let mut target = Self::default();
+        Message::merge(&mut target, data.as_slice())?;
+        let erased: Box<dyn MessageSerde> = Box::new(target);
+        Ok(erased)
+    }
+
+    fn try_encoded(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::with_capacity(Message::encoded_len(self));
+        Message::encode(self, &mut buf)?;
+        Ok(buf)
+    }
+}
+
+const fn entry(type_url: &'static str) -> MessageSerdeDecoderEntry {
+    MessageSerdeDecoderEntry::new(type_url, |buf: &[u8]| {
+        let msg: Event = ::prost::Message::decode(buf)?;
+        Ok(Box::new(msg))
+    })
+}
+
+// Submits `bench.Other000` up to `bench.Other499` besides the event, as in an application with many
+// protos.
+macro_rules! submit_other_types {
+    ($($hundreds:literal)*) => {
+        $(submit_other_types!(@tens $hundreds; 0 1 2 3 4 5 6 7 8 9);)*
+    };
+    (@tens $hundreds:literal; $($tens:literal)*) => {
+        $(submit_other_types!(@units $hundreds $tens; 0 1 2 3 4 5 6 7 8 9);)*
+    };
+    (@units $hundreds:literal $tens:literal; $($units:literal)*) => {
+        $(inventory::submit! {
+            entry(concat!("type.googleapis.com/bench.Other", $hundreds, $tens, $units))
+        })*
+    };
+}
+
+submit_other_types!(0 1 2 3 4);
+
+inventory::submit! {
+    entry("type.googleapis.com/bench.Event")
+}
+
+// The previous implementation: clone the `Any`, scan the `inventory` entries and decode the clone.
+fn serialize_by_clone_and_scan(any: &Any) -> Vec<u8> {
+    let any = any.clone();
+    let entry = inventory::iter::<MessageSerdeDecoderEntry>
+        .into_iter()
+        .find(|entry| entry.type_url == any.type_url)
+        .unwrap();
+    let message = (entry.decoder)(&any.value).unwrap();
+    serde_json::to_vec(message.as_ref()).unwrap()
+}
+
+fn serialize_any(c: &mut Criterion) {
+    let event = Event {
+        name: "login".to_string(),
+        timestamp: Some(Timestamp::date_time(2024, 1, 1, 12, 0, 0).unwrap()),
+        tags: vec!["web".to_string(); 16],
+    };
+    let any = Any::try_pack(event).unwrap();
+    assert_eq!(
+        serde_json::to_vec(&any).unwrap(),
+        serialize_by_clone_and_scan(&any)
+    );
+
+    let mut group = c.benchmark_group("serialize_any");
+    group.bench_function("borrowed_indexed", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&any)).unwrap())
+    });
+    group.bench_function("clone_and_scan", |b| {
+        b.iter(|| serialize_by_clone_and_scan(black_box(&any)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, serialize_any);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+//! Message types that are only known from descriptors loaded at runtime.
+//!
+//! A service that forwards messages it was not compiled with, like a proxy or an event exporter,
+//! can load their descriptors with [`add_file_descriptor_set`]. An `Any` holding one of those
+//! types is then written and read as proto3 JSON instead of an opaque base64 `value`. Types that
+//! are registered through `prost-wkt-build` still take precedence.
+//!
+//! ```rust,ignore
+//! // Created with `protoc --include_imports --descriptor_set_out=events.bin events.proto`.
+//! prost_wkt_types::dynamic::add_file_descriptor_set(&std::fs::read("events.bin")?)?;
+//! ```
+
+use std::fmt;
+use std::sync::{LazyLock, RwLock};
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, MessageDescriptor};
+use serde::ser::{Serialize, Serializer};
+
+static POOL: LazyLock<RwLock<DescriptorPool>> = LazyLock::new(Default::default);
+
+/// Adds the message types of an encoded `FileDescriptorSet`. The set must include the imports
+/// of its files, unless they were added before.
+pub fn add_file_descriptor_set(encoded: &[u8]) -> Result<(), DynamicError> {
+    let mut pool = POOL.write().unwrap_or_else(|error| error.into_inner());
+    pool.decode_file_descriptor_set(encoded)
+        .map_err(|error| DynamicError::new(None, "invalid file descriptor set", error))
+}
+
+/// Returns the descriptors of the message type named by the type url, if they were added with
+/// [`add_file_descriptor_set`].
+pub fn find(type_url: &str) -> Option<MessageDescriptor> {
+    let full_name = match type_url.rfind('/') {
+        Some(pos) => &type_url[pos + 1..],
+        None => return None,
+    };
+    let pool = POOL.read().unwrap_or_else(|error| error.into_inner());
+    pool.get_message_by_name(full_name)
+}
+
+/// A message that is decoded with the descriptors added with [`add_file_descriptor_set`], and
+/// serialized as proto3 JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicMessage {
+    type_url: String,
+    descriptor: MessageDescriptor,
+    message: prost_reflect::DynamicMessage,
+}
+
+impl DynamicMessage {
+    /// Decodes a message of the type named by the type url from the protobuf wire format.
+    pub fn decode(type_url: &str, bytes: &[u8]) -> Result<Self, DynamicError> {
+        let descriptor = find_or_fail(type_url)?;
+        let message = prost_reflect::DynamicMessage::decode(descriptor.clone(), bytes)
+            .map_err(|error| DynamicError::new(Some(type_url), "failed to decode", error))?;
+        Ok(DynamicMessage {
+            type_url: type_url.to_string(),
+            descriptor,
+            message,
+        })
+    }
+
+    /// Parses a message of the type named by the type url from its proto3 JSON representation,
+    /// without the `@type` field.
+    pub fn from_json(type_url: &str, json: serde_json::Value) -> Result<Self, DynamicError> {
+        let descriptor = find_or_fail(type_url)?;
+        let message = prost_reflect::DynamicMessage::deserialize(descriptor.clone(), json)
+            .map_err(|error| DynamicError::new(Some(type_url), "failed to parse JSON", error))?;
+        Ok(DynamicMessage {
+            type_url: type_url.to_string(),
+            descriptor,
+            message,
+        })
+    }
+
+    /// The type url the message was decoded or parsed for.
+    pub fn type_url(&self) -> &str {
+        &self.type_url
+    }
+
+    /// The fully qualified name of the message type, e.g. `my.pkg.Foo`.
+    pub fn full_name(&self) -> &str {
+        self.descriptor.full_name()
+    }
+
+    /// Encodes the message in the protobuf wire format.
+    pub fn encode_to_vec(&self) -> Vec<u8> {
+        self.message.encode_to_vec()
+    }
+}
+
+/// Writes the proto3 JSON representation of the message, without the `@type` field.
+impl Serialize for DynamicMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.message.serialize(serializer)
+    }
+}
+
+fn find_or_fail(type_url: &str) -> Result<MessageDescriptor, DynamicError> {
+    find(type_url).ok_or_else(|| DynamicError {
+        type_url: Some(type_url.to_string()),
+        description: "no descriptor for the message type",
+        source: None,
+    })
+}
+
+/// The error returned when loading descriptors or transcoding a [`DynamicMessage`] fails.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct DynamicError {
+    type_url: Option<String>,
+    description: &'static str,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl DynamicError {
+    fn new(
+        type_url: Option<&str>,
+        description: &'static str,
+        source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        DynamicError {
+            type_url: type_url.map(str::to_string),
+            description,
+            source: Some(Box::new(source)),
+        }
+    }
+
+    /// The type url of the message the error is about, if any.
+    pub fn type_url(&self) -> Option<&str> {
+        self.type_url.as_deref()
+    }
+}
+
+impl fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description)?;
+        if let Some(type_url) = &self.type_url {
+            write!(f, " {type_url}")?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, ": {source}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DynamicError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| source.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
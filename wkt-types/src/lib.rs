@@ -15,4 +15,7 @@ pub use crate::pbempty::*;
 mod pbmask;
 pub use crate::pbmask::*;
 
+#[cfg(feature = "dynamic")]
+pub mod dynamic;
+
 pub use prost_wkt::MessageSerde;
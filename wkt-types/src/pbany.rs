@@ -8,9 +8,37 @@ use prost::{DecodeError, EncodeError, Message, Name};
 
 use std::borrow::Cow;
 
+/// The error returned when packing or unpacking an `Any` fails.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct AnyError {
+    kind: AnyErrorKind,
+    type_url: Option<String>,
     description: Cow<'static, str>,
+    source: Option<AnyErrorSource>,
+}
+
+/// The kind of an [`AnyError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AnyErrorKind {
+    /// No message type is registered for the type url.
+    UnknownTypeUrl,
+    /// The `Any` holds a different message type than the one requested.
+    TypeMismatch,
+    /// The message could not be decoded.
+    Decode,
+    /// The message could not be encoded.
+    Encode,
+    /// Any other error, created with [`AnyError::new`].
+    Other,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum AnyErrorSource {
+    Decode(DecodeError),
+    Encode(EncodeError),
+    RecursionLimit(prost_wkt::recursion::RecursionLimitExceeded),
 }
 
 impl AnyError {
@@ -19,15 +47,88 @@ impl AnyError {
         S: Into<Cow<'static, str>>,
     {
         AnyError {
+            kind: AnyErrorKind::Other,
+            type_url: None,
             description: description.into(),
+            source: None,
+        }
+    }
+
+    fn unknown_type_url(type_url: &str) -> Self {
+        AnyError {
+            kind: AnyErrorKind::UnknownTypeUrl,
+            type_url: Some(type_url.to_string()),
+            description: format!(
+                "Failed to deserialize {type_url}. Make sure prost-wkt-build is executed."
+            )
+            .into(),
+            source: None,
+        }
+    }
+
+    fn type_mismatch(type_url: &str, expected: &str) -> Self {
+        AnyError {
+            kind: AnyErrorKind::TypeMismatch,
+            type_url: Some(type_url.to_string()),
+            description: format!("expected type url {expected} but got {type_url}").into(),
+            source: None,
         }
     }
+
+    fn decode(type_url: &str, error: DecodeError) -> Self {
+        AnyError {
+            type_url: Some(type_url.to_string()),
+            description: format!(
+                "Failed to deserialize {type_url}. Make sure it implements prost::Message. Error reported: {error}"
+            )
+            .into(),
+            ..error.into()
+        }
+    }
+
+    fn recursion_limit(
+        type_url: &str,
+        error: prost_wkt::recursion::RecursionLimitExceeded,
+    ) -> Self {
+        AnyError {
+            kind: AnyErrorKind::Decode,
+            type_url: Some(type_url.to_string()),
+            description: format!("Failed to deserialize {type_url}: {error}").into(),
+            source: Some(AnyErrorSource::RecursionLimit(error)),
+        }
+    }
+
+    fn encode(type_url: &str, error: EncodeError) -> Self {
+        AnyError {
+            type_url: Some(type_url.to_string()),
+            ..error.into()
+        }
+    }
+
+    /// The kind of error.
+    pub fn kind(&self) -> AnyErrorKind {
+        self.kind
+    }
+
+    /// The type url of the `Any` or message the error is about, if known.
+    pub fn type_url(&self) -> Option<&str> {
+        self.type_url.as_deref()
+    }
 }
 
 impl std::error::Error for AnyError {
     fn description(&self) -> &str {
         &self.description
     }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            Some(AnyErrorSource::Decode(error)) => Some(error),
+            Some(AnyErrorSource::Encode(error)) => Some(error),
+            Some(AnyErrorSource::RecursionLimit(error)) => Some(error),
+            None => None,
+        }
+    }
 }
 
 impl std::fmt::Display for AnyError {
@@ -39,13 +140,23 @@ impl std::fmt::Display for AnyError {
 
 impl From<prost::DecodeError> for AnyError {
     fn from(error: DecodeError) -> Self {
-        AnyError::new(format!("Error decoding message: {error:?}"))
+        AnyError {
+            kind: AnyErrorKind::Decode,
+            type_url: None,
+            description: format!("Error decoding message: {error:?}").into(),
+            source: Some(AnyErrorSource::Decode(error)),
+        }
     }
 }
 
 impl From<prost::EncodeError> for AnyError {
     fn from(error: prost::EncodeError) -> Self {
-        AnyError::new(format!("Error encoding message: {error:?}"))
+        AnyError {
+            kind: AnyErrorKind::Encode,
+            type_url: None,
+            description: format!("Error encoding message: {error:?}").into(),
+            source: Some(AnyErrorSource::Encode(error)),
+        }
     }
 }
 
@@ -58,10 +169,25 @@ impl Any {
     where
         T: Message + MessageSerde + Default,
     {
-        let type_url = MessageSerde::type_url(&message).to_string();
+        Self::pack_from(&message)
+    }
+
+    /// Packs a message into an `Any` like [`try_pack`](Self::try_pack), without taking
+    /// ownership of the message. Example usage:
+    ///
+    /// ```ignore
+    /// let any = Any::pack_from(&foo)?;
+    /// ```
+    pub fn pack_from<T>(message: &T) -> Result<Self, AnyError>
+    where
+        T: MessageSerde,
+    {
+        let type_url = MessageSerde::type_url(message).to_string();
         // Serialize the message into a value
         let mut buf = Vec::with_capacity(message.encoded_len());
-        message.encode(&mut buf)?;
+        message
+            .encode(&mut buf)
+            .map_err(|error| AnyError::encode(&type_url, error))?;
         let encoded = Any {
             type_url,
             value: buf,
@@ -69,15 +195,61 @@ impl Any {
         Ok(encoded)
     }
 
+    /// Returns the fully qualified name of the packed message type, e.g. `my.pkg.Foo` for the
+    /// type url `type.googleapis.com/my.pkg.Foo`, or `None` if the type url is invalid.
+    pub fn type_name(&self) -> Option<&str> {
+        TypeUrl::new(&self.type_url).map(|type_url| type_url.full_name)
+    }
+
+    /// Returns `true` if the `Any` holds a message of type `T`.
+    ///
+    /// The type is identified by its fully qualified name, regardless of the domain of the type
+    /// url, unless strict type urls are enabled with
+    /// [`set_strict_type_urls`](prost_wkt::registry::set_strict_type_urls) or a
+    /// [`StrictTypeUrls`](prost_wkt::resolver::StrictTypeUrls) resolver is in place.
+    pub fn is<T: Name>(&self) -> bool {
+        if prost_wkt::resolver::strict_type_urls() {
+            return self.type_url == T::type_url();
+        }
+        self.type_name() == Some(T::full_name().as_str())
+    }
+
+    /// Merges the contents of the `Any` into the given message, after checking that the `Any`
+    /// holds a message of that type, see [`is`](Self::is). Example usage:
+    ///
+    /// ```ignore
+    /// let mut foo = Foo::default();
+    /// any.unpack_into(&mut foo)?;
+    /// ```
+    pub fn unpack_into<T: Message + Name>(&self, target: &mut T) -> Result<(), AnyError> {
+        if !self.is::<T>() {
+            return Err(AnyError::type_mismatch(&self.type_url, &T::type_url()));
+        }
+        target
+            .merge(self.value.as_slice())
+            .map_err(|error| AnyError {
+                type_url: Some(self.type_url.clone()),
+                ..error.into()
+            })
+    }
+
     //#[deprecated(since = "0.5.0", note = "please use `to_msg` instead")]
     /// Unpacks the contents of the `Any` into the provided message type. Example usage:
     ///
     /// ```ignore
     /// let back: Foo = any.unpack_as(Foo::default())?;
     /// ```
+    ///
+    /// The type url is not checked, so any message that happens to decode is accepted. Use
+    /// [`unpack_into`](Self::unpack_into) to make sure the `Any` holds the expected type.
     pub fn unpack_as<T: Message>(self, mut target: T) -> Result<T, AnyError> {
-        let instance = target.merge(self.value.as_slice()).map(|_| target)?;
-        Ok(instance)
+        match target.merge(self.value.as_slice()) {
+            Ok(()) => Ok(target),
+            Err(error) => Err(AnyError {
+                type_url: Some(self.type_url),
+                ..error.into()
+            }),
+        }
     }
 
     /// Unpacks the contents of the `Any` into the `MessageSerde` trait object. Example
@@ -87,20 +259,18 @@ impl Any {
     /// let back: Box<dyn MessageSerde> = any.try_unpack()?;
     /// ```
     pub fn try_unpack(self) -> Result<Box<dyn prost_wkt::MessageSerde>, AnyError> {
-        ::prost_wkt::inventory::iter::<::prost_wkt::MessageSerdeDecoderEntry>
-            .into_iter()
-            .find(|entry| self.type_url == entry.type_url)
-            .ok_or_else(|| format!("Failed to deserialize {}. Make sure prost-wkt-build is executed.", self.type_url))
-            .and_then(|entry| {
-                (entry.decoder)(&self.value).map_err(|error| {
-                    format!(
-                        "Failed to deserialize {}. Make sure it implements prost::Message. Error reported: {}",
-                        self.type_url,
-                        error
-                    )
-                })
-            })
-            .map_err(AnyError::new)
+        self.try_unpack_ref()
+    }
+
+    /// Unpacks the contents of the `Any` into the `MessageSerde` trait object, like
+    /// [`try_unpack`](Self::try_unpack), without taking ownership of the `Any`.
+    pub fn try_unpack_ref(&self) -> Result<Box<dyn prost_wkt::MessageSerde>, AnyError> {
+        // A decoder may unpack the `Any`s it contains, so nesting is limited like in serde.
+        let _guard = prost_wkt::recursion::enter()
+            .map_err(|error| AnyError::recursion_limit(&self.type_url, error))?;
+        let entry = ::prost_wkt::resolver::resolve(&self.type_url)
+            .ok_or_else(|| AnyError::unknown_type_url(&self.type_url))?;
+        (entry.decoder)(&self.value).map_err(|error| AnyError::decode(&self.type_url, error))
     }
 
     /// From Prost's [`Any`] implementation.
@@ -118,7 +288,7 @@ impl Any {
     /// From Prost's [`Any`] implementation.
     /// Decode the given message type `M` from [`Any`], validating that it has
     /// the expected type URL.
-    #[allow(clippy::all)]
+    #[allow(clippy::all, deprecated)]
     pub fn to_msg<M>(&self) -> Result<M, DecodeError>
     where
         M: Default + Name + Sized,
@@ -151,12 +321,61 @@ impl Serialize for Any {
     where
         S: Serializer,
     {
-        match self.clone().try_unpack() {
-            Ok(result) => serde::ser::Serialize::serialize(result.as_ref(), serializer),
-            Err(_) => {
-                let mut state = serializer.serialize_struct("Any", 3)?;
+        // Packed messages can contain an `Any` again, which is unpacked and serialized in turn.
+        let _guard = prost_wkt::recursion::enter().map_err(serde::ser::Error::custom)?;
+        if let Some(full_name) = special_json_name(&self.type_url) {
+            let value = encode_special_json(full_name, &self.value).map_err(|err| {
+                serde::ser::Error::custom(format!("Failed to serialize {}: {err}", self.type_url))
+            })?;
+            let mut state = serializer.serialize_struct("Any", 2)?;
+            state.serialize_field("@type", &self.type_url)?;
+            state.serialize_field("value", &value)?;
+            return state.end();
+        }
+        // Decodes straight from the borrowed bytes. A payload of a registered type that cannot
+        // be decoded is an error, only unknown types are written as base64.
+        let message =
+            match prost_wkt::resolver::resolve(&self.type_url) {
+                Some(entry) => Some((entry.decoder)(&self.value).map_err(|err| {
+                    serde::ser::Error::custom(AnyError::decode(&self.type_url, err))
+                })?),
+                None => None,
+            };
+        match message {
+            Some(result) if result.type_url() == self.type_url => {
+                serde::ser::Serialize::serialize(result.as_ref(), serializer)
+            }
+            Some(result) => {
+                // Resolved by its full name, so keep the type url of the `Any` instead of the
+                // registered one.
+                let mut value =
+                    serde_json::to_value(result.as_ref()).map_err(serde::ser::Error::custom)?;
+                if let Some(object) = value.as_object_mut() {
+                    object.insert("@type".to_string(), self.type_url.clone().into());
+                }
+                value.serialize(serializer)
+            }
+            None => {
+                // Types that are only known from descriptors loaded at runtime, unless a resolver
+                // restricts the types like in `Deserialize`. Messages that cannot be transcoded,
+                // e.g. because they hold an `Any` of a type missing from the descriptors, are
+                // written like unknown types.
+                #[cfg(feature = "dynamic")]
+                if !prost_wkt::resolver::is_scoped() {
+                    let value = crate::dynamic::DynamicMessage::decode(&self.type_url, &self.value)
+                        .ok()
+                        .and_then(|message| serde_json::to_value(&message).ok());
+                    if let Some(mut value) = value {
+                        if let Some(object) = value.as_object_mut() {
+                            object.insert("@type".to_string(), self.type_url.clone().into());
+                        }
+                        return value.serialize(serializer);
+                    }
+                }
+                let mut state = serializer.serialize_struct("Any", 2)?;
                 state.serialize_field("@type", &self.type_url)?;
-                state.serialize_field("value", &self.value)?;
+                // Written as base64, so that `Deserialize` can restore the payload unchanged.
+                state.serialize_field("value", &prost_wkt::json::encode_base64(&self.value))?;
                 state.end()
             }
         }
@@ -168,9 +387,87 @@ impl<'de> Deserialize<'de> for Any {
     where
         D: Deserializer<'de>,
     {
-        let erased: Box<dyn prost_wkt::MessageSerde> =
-            serde::de::Deserialize::deserialize(deserializer)?;
-        let type_url = erased.type_url().to_string();
+        // The resolver is consulted first, by default the registry, as it also knows the types
+        // that were registered at runtime or installed with the `static-registry` feature. Other
+        // types are left to typetag, unless a resolver was put in place to restrict the types.
+        let _guard = prost_wkt::recursion::enter().map_err(serde::de::Error::custom)?;
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        let type_url = value
+            .get("@type")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+        if let Some(type_url) = type_url.as_deref() {
+            if let Some(full_name) = special_json_name(type_url) {
+                let value =
+                    decode_special_json(full_name, value.get("value").cloned()).map_err(|err| {
+                        serde::de::Error::custom(format!("Failed to deserialize {type_url}: {err}"))
+                    })?;
+                return Ok(Any {
+                    type_url: type_url.to_string(),
+                    value,
+                });
+            }
+        }
+        let entry = type_url.as_deref().and_then(prost_wkt::resolver::resolve);
+        let (erased, type_url) = match (entry, type_url) {
+            (Some(entry), Some(type_url)) => {
+                // The entry may have been resolved by its full name, so its own type url is
+                // passed on, as the default deserializer looks up the typetag name by it.
+                if let Some(object) = value.as_object_mut() {
+                    object.insert("@type".to_string(), entry.type_url.into());
+                }
+                // An `Any` written as an unknown type, with a base64 `value`, e.g. by a process
+                // that did not know the type. Unless the message has a field named `value`, the
+                // payload is decoded instead of replacing it with the default message.
+                let payload = unknown_payload(&value).filter(|payload| !payload.is_empty());
+                match (entry.deserialize(value), payload) {
+                    (Ok(erased), Some(payload)) if erased.encoded_len() == 0 => {
+                        return decode_payload(entry, type_url, payload);
+                    }
+                    (Ok(erased), _) => (erased, type_url),
+                    (Err(_), Some(payload)) => return decode_payload(entry, type_url, payload),
+                    (Err(err), None) => return Err(serde::de::Error::custom(err)),
+                }
+            }
+            (None, Some(type_url)) if prost_wkt::resolver::is_scoped() => {
+                return Err(serde::de::Error::custom(format!(
+                    "Failed to deserialize {type_url}. The type is not accepted by the resolver."
+                )));
+            }
+            (_, type_url) => match Box::<dyn MessageSerde>::deserialize(&value) {
+                Ok(erased) => {
+                    let type_url = erased.type_url().to_string();
+                    (erased, type_url)
+                }
+                Err(err) => {
+                    // A type that is only known from descriptors loaded at runtime.
+                    #[cfg(feature = "dynamic")]
+                    let err = match type_url.as_deref() {
+                        Some(type_url) if crate::dynamic::find(type_url).is_some() => {
+                            let mut json = value.clone();
+                            if let Some(object) = json.as_object_mut() {
+                                object.remove("@type");
+                            }
+                            match crate::dynamic::DynamicMessage::from_json(type_url, json) {
+                                Ok(message) => {
+                                    return Ok(Any {
+                                        type_url: type_url.to_string(),
+                                        value: message.encode_to_vec(),
+                                    })
+                                }
+                                Err(err) => err.to_string(),
+                            }
+                        }
+                        _ => err.to_string(),
+                    };
+                    // An unknown type, as written by `Serialize` with a base64 `value`.
+                    return match (type_url, unknown_payload(&value)) {
+                        (Some(type_url), Some(value)) => Ok(Any { type_url, value }),
+                        _ => Err(serde::de::Error::custom(err)),
+                    };
+                }
+            },
+        };
         let value = erased.try_encoded().map_err(|err| {
             serde::de::Error::custom(format!("Failed to encode message: {err:?}"))
         })?;
@@ -178,6 +475,150 @@ impl<'de> Deserialize<'de> for Any {
     }
 }
 
+// Checks that the payload of an `Any` of a registered type that was written with a base64 `value`
+// decodes, and keeps it unchanged.
+fn decode_payload<E: serde::de::Error>(
+    entry: &prost_wkt::MessageSerdeDecoderEntry,
+    type_url: String,
+    payload: Vec<u8>,
+) -> Result<Any, E> {
+    (entry.decoder)(&payload)
+        .map_err(|err| serde::de::Error::custom(AnyError::decode(&type_url, err)))?;
+    Ok(Any {
+        type_url,
+        value: payload,
+    })
+}
+
+// Returns the payload of an `Any` with an unknown type, which is written as an object with just
+// `@type` and a base64 `value`.
+fn unknown_payload(json: &serde_json::Value) -> Option<Vec<u8>> {
+    let object = json.as_object()?;
+    if object.len() != 2 {
+        return None;
+    }
+    let value = object.get("value")?.as_str()?;
+    prost_wkt::json::decode_base64(value).ok()
+}
+
+// The well-known types whose JSON representation is not an object. Packed into an `Any`, the
+// JSON of such a message is put into a `value` field next to `@type`, e.g.
+// `{"@type": "type.googleapis.com/google.protobuf.Duration", "value": "1.5s"}`.
+const SPECIAL_JSON_TYPES: &[&str] = &[
+    "google.protobuf.Any",
+    "google.protobuf.BoolValue",
+    "google.protobuf.BytesValue",
+    "google.protobuf.DoubleValue",
+    "google.protobuf.Duration",
+    "google.protobuf.FieldMask",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int32Value",
+    "google.protobuf.Int64Value",
+    "google.protobuf.ListValue",
+    "google.protobuf.StringValue",
+    "google.protobuf.Struct",
+    "google.protobuf.Timestamp",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.UInt64Value",
+    "google.protobuf.Value",
+];
+
+// Returns the full name of the well-known type, if the type url names one with a special JSON
+// representation. Type urls are matched by full name like the registry does, unless strict type
+// urls are enabled globally or by the resolver in place.
+fn special_json_name(type_url: &str) -> Option<&'static str> {
+    let full_name = TypeUrl::new(type_url)?.full_name;
+    let name = SPECIAL_JSON_TYPES.iter().find(|name| **name == full_name)?;
+    if prost_wkt::resolver::strict_type_urls()
+        && type_url.strip_suffix(full_name) != Some("type.googleapis.com/")
+    {
+        return None;
+    }
+    Some(name)
+}
+
+fn encode_special_json(full_name: &str, bytes: &[u8]) -> Result<serde_json::Value, String> {
+    use prost_wkt::json::{Bytes, Float, Int32, Int64, ProtoJson, SerializeAs};
+
+    fn message<T: Message + Default + Serialize>(
+        bytes: &[u8],
+    ) -> Result<serde_json::Value, String> {
+        let message = T::decode(bytes).map_err(|err| err.to_string())?;
+        serde_json::to_value(&message).map_err(|err| err.to_string())
+    }
+
+    fn wrapper<E, T: Message + Default + ProtoJson<E>>(
+        bytes: &[u8],
+    ) -> Result<serde_json::Value, String> {
+        let value = T::decode(bytes).map_err(|err| err.to_string())?;
+        serde_json::to_value(SerializeAs::<E, T>::new(&value)).map_err(|err| err.to_string())
+    }
+
+    match full_name {
+        "google.protobuf.Any" => message::<Any>(bytes),
+        "google.protobuf.Duration" => message::<crate::Duration>(bytes),
+        "google.protobuf.FieldMask" => message::<crate::FieldMask>(bytes),
+        "google.protobuf.ListValue" => message::<crate::ListValue>(bytes),
+        "google.protobuf.Struct" => message::<crate::Struct>(bytes),
+        "google.protobuf.Timestamp" => message::<crate::Timestamp>(bytes),
+        "google.protobuf.Value" => message::<crate::Value>(bytes),
+        "google.protobuf.BoolValue" => message::<bool>(bytes),
+        "google.protobuf.StringValue" => message::<String>(bytes),
+        "google.protobuf.BytesValue" => wrapper::<Bytes, Vec<u8>>(bytes),
+        "google.protobuf.DoubleValue" => wrapper::<Float, f64>(bytes),
+        "google.protobuf.FloatValue" => wrapper::<Float, f32>(bytes),
+        "google.protobuf.Int32Value" => wrapper::<Int32, i32>(bytes),
+        "google.protobuf.Int64Value" => wrapper::<Int64, i64>(bytes),
+        "google.protobuf.UInt32Value" => wrapper::<Int32, u32>(bytes),
+        "google.protobuf.UInt64Value" => wrapper::<Int64, u64>(bytes),
+        _ => Err(format!("{full_name} has no special JSON representation")),
+    }
+}
+
+// A missing `value` is the default message, which is encoded as no bytes at all.
+fn decode_special_json(
+    full_name: &str,
+    json: Option<serde_json::Value>,
+) -> Result<Vec<u8>, String> {
+    use prost_wkt::json::{Bytes, DeserializeAs, Float, Int32, Int64, ProtoJson};
+
+    fn message<T: Message + serde::de::DeserializeOwned>(
+        json: serde_json::Value,
+    ) -> Result<Vec<u8>, String> {
+        let message: T = serde_json::from_value(json).map_err(|err| err.to_string())?;
+        Ok(message.encode_to_vec())
+    }
+
+    fn wrapper<E, T: Message + ProtoJson<E>>(json: serde_json::Value) -> Result<Vec<u8>, String> {
+        let value: DeserializeAs<E, T> =
+            serde_json::from_value(json).map_err(|err| err.to_string())?;
+        Ok(value.into_inner().encode_to_vec())
+    }
+
+    let Some(json) = json else {
+        return Ok(Vec::new());
+    };
+    match full_name {
+        "google.protobuf.Any" => message::<Any>(json),
+        "google.protobuf.Duration" => message::<crate::Duration>(json),
+        "google.protobuf.FieldMask" => message::<crate::FieldMask>(json),
+        "google.protobuf.ListValue" => message::<crate::ListValue>(json),
+        "google.protobuf.Struct" => message::<crate::Struct>(json),
+        "google.protobuf.Timestamp" => message::<crate::Timestamp>(json),
+        "google.protobuf.Value" => message::<crate::Value>(json),
+        "google.protobuf.BoolValue" => message::<bool>(json),
+        "google.protobuf.StringValue" => message::<String>(json),
+        "google.protobuf.BytesValue" => wrapper::<Bytes, Vec<u8>>(json),
+        "google.protobuf.DoubleValue" => wrapper::<Float, f64>(json),
+        "google.protobuf.FloatValue" => wrapper::<Float, f32>(json),
+        "google.protobuf.Int32Value" => wrapper::<Int32, i32>(json),
+        "google.protobuf.Int64Value" => wrapper::<Int64, i64>(json),
+        "google.protobuf.UInt32Value" => wrapper::<Int32, u32>(json),
+        "google.protobuf.UInt64Value" => wrapper::<Int64, u64>(json),
+        _ => Err(format!("{full_name} has no special JSON representation")),
+    }
+}
+
 #[cfg(feature = "schemars")]
 mod schemars_impl {
     use super::Any;
@@ -7,20 +7,55 @@ use std::fmt;
 
 include!(concat!(env!("OUT_DIR"), "/pbstruct/google.protobuf.rs"));
 
+/// The error returned when converting a `Value` into a Rust type fails.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct ValueError {
+    kind: ValueErrorKind,
     description: Cow<'static, str>,
 }
 
+/// The kind of a [`ValueError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ValueErrorKind {
+    /// The `Value` holds a different kind of value than the one requested.
+    KindMismatch,
+    /// The `Value` holds no value at all.
+    Empty,
+    /// Any other error, created with [`ValueError::new`].
+    Other,
+}
+
 impl ValueError {
     pub fn new<S>(description: S) -> Self
     where
         S: Into<Cow<'static, str>>,
     {
         ValueError {
+            kind: ValueErrorKind::Other,
+            description: description.into(),
+        }
+    }
+
+    fn kind_mismatch(description: &'static str) -> Self {
+        ValueError {
+            kind: ValueErrorKind::KindMismatch,
             description: description.into(),
         }
     }
+
+    fn empty(description: &'static str) -> Self {
+        ValueError {
+            kind: ValueErrorKind::Empty,
+            description: description.into(),
+        }
+    }
+
+    /// The kind of error.
+    pub fn kind(&self) -> ValueErrorKind {
+        self.kind
+    }
 }
 
 impl std::error::Error for ValueError {
@@ -77,10 +112,10 @@ impl TryFrom<Value> for f64 {
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value.kind {
             Some(value::Kind::NumberValue(num)) => Ok(num),
-            Some(_other) => Err(ValueError::new(
+            Some(_other) => Err(ValueError::kind_mismatch(
                 "Cannot convert to f64 because this is not a ValueNumber.",
             )),
-            _ => Err(ValueError::new(
+            _ => Err(ValueError::empty(
                 "Conversion to f64 failed because value is empty!",
             )),
         }
@@ -100,10 +135,10 @@ impl TryFrom<Value> for String {
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value.kind {
             Some(value::Kind::StringValue(string)) => Ok(string),
-            Some(_other) => Err(ValueError::new(
+            Some(_other) => Err(ValueError::kind_mismatch(
                 "Cannot convert to String because this is not a StringValue.",
             )),
-            _ => Err(ValueError::new(
+            _ => Err(ValueError::empty(
                 "Conversion to String failed because value is empty!",
             )),
         }
@@ -123,10 +158,10 @@ impl TryFrom<Value> for bool {
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value.kind {
             Some(value::Kind::BoolValue(b)) => Ok(b),
-            Some(_other) => Err(ValueError::new(
+            Some(_other) => Err(ValueError::kind_mismatch(
                 "Cannot convert to bool because this is not a BoolValue.",
             )),
-            _ => Err(ValueError::new(
+            _ => Err(ValueError::empty(
                 "Conversion to bool failed because value is empty!",
             )),
         }
@@ -147,10 +182,10 @@ impl TryFrom<Value> for std::collections::HashMap<std::string::String, Value> {
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value.kind {
             Some(value::Kind::StructValue(s)) => Ok(s.fields),
-            Some(_other) => Err(ValueError::new(
+            Some(_other) => Err(ValueError::kind_mismatch(
                 "Cannot convert to HashMap<String, Value> because this is not a StructValue.",
             )),
-            _ => Err(ValueError::new(
+            _ => Err(ValueError::empty(
                 "Conversion to HashMap<String, Value> failed because value is empty!",
             )),
         }
@@ -171,10 +206,10 @@ impl TryFrom<Value> for std::vec::Vec<Value> {
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value.kind {
             Some(value::Kind::ListValue(list)) => Ok(list.values),
-            Some(_other) => Err(ValueError::new(
+            Some(_other) => Err(ValueError::kind_mismatch(
                 "Cannot convert to Vec<Value> because this is not a ListValue.",
             )),
-            _ => Err(ValueError::new(
+            _ => Err(ValueError::empty(
                 "Conversion to Vec<Value> failed because value is empty!",
             )),
         }
@@ -236,6 +271,7 @@ impl<'de> Visitor<'de> for ListValueVisitor {
     where
         A: SeqAccess<'de>,
     {
+        let _guard = prost_wkt::recursion::enter().map_err(de::Error::custom)?;
         let mut values: Vec<Value> = Vec::new();
         while let Some(el) = seq.next_element()? {
             values.push(el)
@@ -265,6 +301,7 @@ impl<'de> Visitor<'de> for StructVisitor {
     where
         A: MapAccess<'de>,
     {
+        let _guard = prost_wkt::recursion::enter().map_err(de::Error::custom)?;
         let mut fields: std::collections::HashMap<String, Value> = std::collections::HashMap::new();
         while let Some((key, value)) = map.next_entry::<String, Value>()? {
             fields.insert(key, value);
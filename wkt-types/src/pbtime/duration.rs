@@ -1,7 +1,7 @@
 use super::*;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// FROM prost-types/src/duration.rs
+// FROM prost-types/src/duration.rs
 ////////////////////////////////////////////////////////////////////////////////
 
 impl Duration {
@@ -172,7 +172,7 @@ impl FromStr for Duration {
 }
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Chrono conversion
+// Chrono conversion
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Converts proto duration to chrono's Duration
@@ -251,7 +251,7 @@ impl<'de> Deserialize<'de> for Duration {
                     Some(fraction) => {
                         let fraction = format!("{:0<9}", fraction); // Pad fraction to nanoseconds
                         let nanos = fraction.parse().map_err(de::Error::custom)?;
-                        if nanos < 0 || nanos >= NANOS_PER_SECOND as i32 {
+                        if !(0..NANOS_PER_SECOND).contains(&nanos) {
                             return Err(de::Error::custom(format!(
                                 "Fractional nanoseconds out of range: {}",
                                 nanos
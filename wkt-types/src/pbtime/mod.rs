@@ -10,7 +10,6 @@ use core::convert::TryFrom;
 use core::str::FromStr;
 use core::time;
 use core::*;
-use std::convert::TryInto;
 
 use chrono::prelude::*;
 
@@ -1,7 +1,7 @@
 use super::*;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// FROM prost-types/src/timestamp.rs
+// FROM prost-types/src/timestamp.rs
 ////////////////////////////////////////////////////////////////////////////////
 
 impl Timestamp {
@@ -225,7 +225,7 @@ impl fmt::Display for Timestamp {
 }
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Chrono conversion
+// Chrono conversion
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Converts chrono's `NaiveDateTime` to `Timestamp`..
@@ -272,7 +272,7 @@ impl Serialize for Timestamp {
             nanos: self.nanos,
         };
         ts.normalize();
-        let dt: DateTime<Utc> = ts.try_into().map_err(serde::ser::Error::custom)?;
+        let dt: DateTime<Utc> = ts.into();
         serializer.serialize_str(format!("{dt:?}").as_str())
     }
 }
@@ -0,0 +1,203 @@
+#![cfg(feature = "dynamic")]
+
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet};
+use prost_wkt_types::dynamic::{self, DynamicMessage};
+use prost_wkt_types::Any;
+
+// The same message as `events.v1.Event` in the descriptors below, used only to encode payloads.
+// It is not registered, so the binary does not know the type.
+#[derive(Clone, PartialEq, Message)]
+struct Event {
+    #[prost(string, tag = "1")]
+    user_name: String,
+    #[prost(int64, tag = "2")]
+    count: i64,
+    #[prost(string, repeated, tag = "3")]
+    tags: Vec<String>,
+}
+
+fn field(name: &str, number: i32, r#type: Type, label: Label) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        r#type: Some(r#type as i32),
+        label: Some(label as i32),
+        ..Default::default()
+    }
+}
+
+fn descriptor_set() -> Vec<u8> {
+    FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("events/v1/event.proto".to_string()),
+            package: Some("events.v1".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Event".to_string()),
+                field: vec![
+                    field("user_name", 1, Type::String, Label::Optional),
+                    field("count", 2, Type::Int64, Label::Optional),
+                    field("tags", 3, Type::String, Label::Repeated),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+    .encode_to_vec()
+}
+
+#[test]
+fn transcode_unregistered_any_with_descriptors() {
+    let event = Event {
+        user_name: "ann".to_string(),
+        count: 3,
+        tags: vec!["a".to_string(), "b".to_string()],
+    };
+    let any = Any {
+        type_url: "type.googleapis.com/events.v1.Event".to_string(),
+        value: event.encode_to_vec(),
+    };
+
+    // Without descriptors the payload is opaque.
+    let opaque = serde_json::to_value(&any).unwrap();
+    assert!(opaque["value"].is_string());
+    let error = DynamicMessage::decode(&any.type_url, &any.value).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "no descriptor for the message type type.googleapis.com/events.v1.Event"
+    );
+
+    dynamic::add_file_descriptor_set(&descriptor_set()).unwrap();
+
+    let json = serde_json::to_value(&any).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "@type": "type.googleapis.com/events.v1.Event",
+            "userName": "ann",
+            "count": "3",
+            "tags": ["a", "b"]
+        })
+    );
+    let back: Any = serde_json::from_value(json).unwrap();
+    assert_eq!(back, any);
+
+    // Payloads written before the descriptors were added are still read back.
+    let back: Any = serde_json::from_value(opaque).unwrap();
+    assert_eq!(back, any);
+
+    let message = DynamicMessage::decode(&any.type_url, &any.value).unwrap();
+    assert_eq!(message.full_name(), "events.v1.Event");
+    assert_eq!(message.encode_to_vec(), any.value);
+
+    let error = serde_json::from_value::<Any>(serde_json::json!({
+        "@type": "type.googleapis.com/events.v1.Event",
+        "count": "many"
+    }))
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("failed to parse JSON type.googleapis.com/events.v1.Event: "));
+}
+
+fn message_field(name: &str, number: i32, type_name: &str) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        type_name: Some(type_name.to_string()),
+        ..field(name, number, Type::Message, Label::Optional)
+    }
+}
+
+// `envelopes.v1.Envelope` holds an `Any`, and `envelopes.v1.Note` is a plain message.
+fn envelope_descriptor_set() -> Vec<u8> {
+    FileDescriptorSet {
+        file: vec![
+            FileDescriptorProto {
+                name: Some("google/protobuf/any.proto".to_string()),
+                package: Some("google.protobuf".to_string()),
+                syntax: Some("proto3".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Any".to_string()),
+                    field: vec![
+                        field("type_url", 1, Type::String, Label::Optional),
+                        field("value", 2, Type::Bytes, Label::Optional),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            FileDescriptorProto {
+                name: Some("envelopes/v1/envelope.proto".to_string()),
+                package: Some("envelopes.v1".to_string()),
+                dependency: vec!["google/protobuf/any.proto".to_string()],
+                syntax: Some("proto3".to_string()),
+                message_type: vec![
+                    DescriptorProto {
+                        name: Some("Envelope".to_string()),
+                        field: vec![message_field("payload", 1, ".google.protobuf.Any")],
+                        ..Default::default()
+                    },
+                    DescriptorProto {
+                        name: Some("Note".to_string()),
+                        field: vec![field("text", 1, Type::String, Label::Optional)],
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        ],
+    }
+    .encode_to_vec()
+}
+
+#[test]
+fn write_untranscodable_payloads_as_base64() {
+    dynamic::add_file_descriptor_set(&envelope_descriptor_set()).unwrap();
+
+    // The envelope holds an `Any` of a type that is missing from the descriptors.
+    let payload = prost_types::Any {
+        type_url: "type.googleapis.com/envelopes.v1.Missing".to_string(),
+        value: vec![8, 1],
+    }
+    .encode_to_vec();
+    let mut value = vec![10, payload.len() as u8];
+    value.extend(payload);
+    let any = Any {
+        type_url: "type.googleapis.com/envelopes.v1.Envelope".to_string(),
+        value,
+    };
+
+    let json = serde_json::to_value(&any).unwrap();
+    assert_eq!(json["@type"], "type.googleapis.com/envelopes.v1.Envelope");
+    assert!(json["value"].is_string());
+    let back: Any = serde_json::from_value(json).unwrap();
+    assert_eq!(back, any);
+}
+
+#[test]
+fn skip_descriptors_with_scoped_resolver() {
+    use prost_wkt::registry::Registry;
+    use prost_wkt::resolver::{with_resolver, WithResolver};
+
+    dynamic::add_file_descriptor_set(&envelope_descriptor_set()).unwrap();
+    let any = Any {
+        type_url: "type.googleapis.com/envelopes.v1.Note".to_string(),
+        value: vec![10, 2, 104, 105],
+    };
+    assert_eq!(
+        serde_json::to_value(&any).unwrap(),
+        serde_json::json!({
+            "@type": "type.googleapis.com/envelopes.v1.Note",
+            "text": "hi"
+        })
+    );
+
+    let registry = Registry::new();
+    let json = serde_json::to_value(WithResolver(&registry, &any)).unwrap();
+    assert!(json["value"].is_string());
+    assert!(with_resolver(&registry, || serde_json::from_value::<Any>(json.clone())).is_err());
+    let back: Any = serde_json::from_value(json).unwrap();
+    assert_eq!(back, any);
+}
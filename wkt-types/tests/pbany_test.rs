@@ -60,13 +60,10 @@ impl prost_wkt::MessageSerde for Foo {
 }
 
 ::prost_wkt::inventory::submit! {
-    ::prost_wkt::MessageSerdeDecoderEntry {
-        type_url: "type.googleapis.com/any.test.Foo",
-        decoder: |buf: &[u8]| {
-            let msg: Foo = ::prost::Message::decode(buf)?;
-            Ok(Box::new(msg))
-        }
-    }
+    ::prost_wkt::MessageSerdeDecoderEntry::new("type.googleapis.com/any.test.Foo", |buf: &[u8]| {
+        let msg: Foo = ::prost::Message::decode(buf)?;
+        Ok(Box::new(msg))
+    })
 }
 
 fn create_struct() -> Value {
@@ -185,3 +182,452 @@ fn test_any_unpack() {
     println!("Unpacked: {foo:?}");
     assert_eq!(foo.string, "hello payload");
 }
+
+#[test]
+fn test_any_resolve_by_full_name() {
+    let payload = Foo {
+        string: String::from("other domain"),
+        ..Default::default()
+    };
+    let mut any = prost_wkt_types::Any::try_pack(payload.clone()).unwrap();
+    any.type_url = "example.com/any.test.Foo".to_string();
+
+    let unpacked = any.clone().try_unpack().unwrap();
+    assert_eq!(unpacked.downcast_ref::<Foo>(), Some(&payload));
+
+    let json = serde_json::to_value(&any).unwrap();
+    assert_eq!(json["@type"], "example.com/any.test.Foo");
+    assert_eq!(json["string"], "other domain");
+    let back: prost_wkt_types::Any = serde_json::from_value(json).unwrap();
+    assert_eq!(back, any);
+}
+
+#[test]
+fn test_any_strict_resolver_applies_to_wkt() {
+    use prost_wkt::resolver::{with_resolver, GlobalRegistry, StrictTypeUrls, WithResolver};
+
+    let any = Any {
+        type_url: "example.com/google.protobuf.Duration".to_string(),
+        value: Duration {
+            seconds: 1,
+            nanos: 0,
+        }
+        .encode_to_vec(),
+    };
+    let json = serde_json::to_value(&any).unwrap();
+    assert_eq!(json["value"], "1.000000000s");
+
+    let strict = StrictTypeUrls(GlobalRegistry);
+    let unresolved = serde_json::to_value(WithResolver(&strict, &any)).unwrap();
+    assert_ne!(unresolved["value"], "1.000000000s");
+    assert!(with_resolver(&strict, || serde_json::from_value::<Any>(json.clone())).is_err());
+}
+
+fn assert_wkt_json<T>(full_name: &str, message: T, value: serde_json::Value)
+where
+    T: Message + Default + PartialEq + std::fmt::Debug,
+{
+    let any = Any {
+        type_url: format!("type.googleapis.com/{full_name}"),
+        value: message.encode_to_vec(),
+    };
+    let json = serde_json::to_value(&any).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "@type": any.type_url, "value": value })
+    );
+    // Compare the decoded messages, as the encoding of maps is not deterministic.
+    let back: Any = serde_json::from_value(json).unwrap();
+    assert_eq!(back.type_url, any.type_url);
+    assert_eq!(T::decode(back.value.as_slice()).unwrap(), message);
+}
+
+#[test]
+fn test_any_wkt_timestamp() {
+    assert_wkt_json(
+        "google.protobuf.Timestamp",
+        Timestamp::date_time(2020, 1, 1, 12, 30, 0).unwrap(),
+        serde_json::json!("2020-01-01T12:30:00Z"),
+    );
+}
+
+#[test]
+fn test_any_wkt_duration() {
+    assert_wkt_json(
+        "google.protobuf.Duration",
+        Duration {
+            seconds: 1,
+            nanos: 500_000_000,
+        },
+        serde_json::json!("1.500000000s"),
+    );
+}
+
+#[test]
+fn test_any_wkt_struct_value_and_list() {
+    let value = create_struct();
+    let json = serde_json::to_value(&value).unwrap();
+    assert_wkt_json("google.protobuf.Value", value.clone(), json.clone());
+    assert_wkt_json(
+        "google.protobuf.Value",
+        Value::null(),
+        serde_json::Value::Null,
+    );
+
+    let Some(value::Kind::StructValue(pb_struct)) = value.kind else {
+        panic!("expected a struct");
+    };
+    assert_wkt_json("google.protobuf.Struct", pb_struct, json);
+
+    let list = ListValue {
+        values: vec![Value::from(1.0), Value::from(String::from("two"))],
+    };
+    assert_wkt_json(
+        "google.protobuf.ListValue",
+        list,
+        serde_json::json!([1.0, "two"]),
+    );
+}
+
+#[test]
+fn test_any_wkt_field_mask() {
+    let mask = FieldMask {
+        paths: vec!["foo.bar".to_string(), "baz".to_string()],
+    };
+    let json = serde_json::to_value(&mask).unwrap();
+    assert_wkt_json("google.protobuf.FieldMask", mask, json);
+}
+
+#[test]
+fn test_any_wkt_any() {
+    let inner = Any {
+        type_url: "type.googleapis.com/google.protobuf.Duration".to_string(),
+        value: Duration {
+            seconds: 3,
+            nanos: 0,
+        }
+        .encode_to_vec(),
+    };
+    assert_wkt_json(
+        "google.protobuf.Any",
+        inner,
+        serde_json::json!({
+            "@type": "type.googleapis.com/google.protobuf.Duration",
+            "value": "3.000000000s"
+        }),
+    );
+}
+
+#[test]
+fn test_any_wkt_wrappers() {
+    assert_wkt_json("google.protobuf.BoolValue", true, serde_json::json!(true));
+    assert_wkt_json(
+        "google.protobuf.StringValue",
+        String::from("hello"),
+        serde_json::json!("hello"),
+    );
+    assert_wkt_json(
+        "google.protobuf.BytesValue",
+        b"hello".to_vec(),
+        serde_json::json!("aGVsbG8="),
+    );
+    assert_wkt_json(
+        "google.protobuf.DoubleValue",
+        1.5f64,
+        serde_json::json!(1.5),
+    );
+    assert_wkt_json(
+        "google.protobuf.FloatValue",
+        -2.5f32,
+        serde_json::json!(-2.5),
+    );
+    assert_wkt_json("google.protobuf.Int32Value", -7i32, serde_json::json!(-7));
+    assert_wkt_json("google.protobuf.UInt32Value", 7u32, serde_json::json!(7));
+    assert_wkt_json("google.protobuf.Int64Value", -7i64, serde_json::json!("-7"));
+    assert_wkt_json("google.protobuf.UInt64Value", 7u64, serde_json::json!("7"));
+}
+
+#[test]
+fn test_any_wkt_missing_value() {
+    let any: Any = serde_json::from_value(serde_json::json!({
+        "@type": "type.googleapis.com/google.protobuf.Int64Value"
+    }))
+    .unwrap();
+    assert!(any.value.is_empty());
+
+    let any: Any = serde_json::from_value(serde_json::json!({
+        "@type": "type.googleapis.com/google.protobuf.Int64Value",
+        "value": 42
+    }))
+    .unwrap();
+    assert_eq!(any.value, 42i64.encode_to_vec());
+}
+
+#[test]
+fn test_any_unknown_type_round_trip() {
+    let any = Any {
+        type_url: "type.googleapis.com/any.test.Unknown".to_string(),
+        value: vec![8, 150, 1, 18, 2, 104, 105],
+    };
+    let json = serde_json::to_value(&any).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "@type": "type.googleapis.com/any.test.Unknown",
+            "value": "CJYBEgJoaQ=="
+        })
+    );
+    let back: Any = serde_json::from_value(json).unwrap();
+    assert_eq!(back, any);
+
+    let error = serde_json::from_value::<Any>(serde_json::json!({
+        "@type": "type.googleapis.com/any.test.Unknown",
+        "field": 1
+    }))
+    .unwrap_err();
+    assert!(error.to_string().contains("any.test.Unknown"));
+}
+
+#[test]
+fn test_any_registered_type_payload() {
+    let foo = Foo {
+        string: String::from("forwarded"),
+        ..Default::default()
+    };
+    let payload = foo.encode_to_vec();
+    let back: Any = serde_json::from_value(serde_json::json!({
+        "@type": "type.googleapis.com/any.test.Foo",
+        "value": prost_wkt::json::encode_base64(&payload)
+    }))
+    .unwrap();
+    assert_eq!(back.value, payload);
+
+    // A truncated string field.
+    let broken = vec![10, 5, 104, 105];
+    let error = serde_json::from_value::<Any>(serde_json::json!({
+        "@type": "type.googleapis.com/any.test.Foo",
+        "value": prost_wkt::json::encode_base64(&broken)
+    }))
+    .unwrap_err();
+    assert!(error.to_string().contains("Failed to deserialize"));
+
+    let any = Any {
+        type_url: "type.googleapis.com/any.test.Foo".to_string(),
+        value: broken,
+    };
+    let error = serde_json::to_value(&any).unwrap_err();
+    assert!(error.to_string().contains("Failed to deserialize"));
+}
+
+#[test]
+fn test_any_nesting_limit() {
+    let nested = |depth: usize| {
+        let mut any = Any::try_pack(Foo::default()).unwrap();
+        for _ in 0..depth {
+            any = Any::from_msg(&any).unwrap();
+        }
+        any
+    };
+
+    let json = serde_json::to_value(nested(20)).unwrap();
+    let back: Any = serde_json::from_value(json).unwrap();
+    assert_eq!(back, nested(20));
+
+    let error = serde_json::to_value(nested(150)).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("recursion limit of 100 exceeded"));
+
+    let mut json = serde_json::to_value(Any::try_pack(Foo::default()).unwrap()).unwrap();
+    for _ in 0..150 {
+        json = serde_json::json!({
+            "@type": "type.googleapis.com/google.protobuf.Any",
+            "value": json,
+        });
+    }
+    let error = serde_json::from_value::<Any>(json).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("recursion limit of 100 exceeded"));
+}
+
+// A type whose decoder unpacks the `Any` it wraps, like a decoder that resolves its contents
+// eagerly.
+#[allow(deprecated)]
+static UNWRAPPING_ENTRY: MessageSerdeDecoderEntry =
+    MessageSerdeDecoderEntry::new("type.googleapis.com/any.test.Unwrapping", |buf: &[u8]| {
+        let inner: Any = Message::decode(buf)?;
+        inner
+            .try_unpack()
+            .map_err(|error| DecodeError::new(error.to_string()))
+    });
+
+#[test]
+fn test_any_unpack_nesting_limit() {
+    use prost_wkt::registry::Registry;
+    use prost_wkt::resolver::with_resolver;
+
+    let nested = |depth: usize| {
+        let mut any = Any::try_pack(Foo::default()).unwrap();
+        for _ in 0..depth {
+            any = Any {
+                type_url: UNWRAPPING_ENTRY.type_url.to_string(),
+                value: any.encode_to_vec(),
+            };
+        }
+        any
+    };
+
+    let mut registry = Registry::new();
+    registry.register(&FOO_ENTRY).unwrap();
+    registry.register(&UNWRAPPING_ENTRY).unwrap();
+    with_resolver(&registry, || {
+        let unpacked = nested(20).try_unpack().unwrap();
+        assert_eq!(unpacked.downcast_ref::<Foo>(), Some(&Foo::default()));
+
+        let error = nested(150).try_unpack().unwrap_err();
+        assert_eq!(error.kind(), prost_wkt_types::AnyErrorKind::Decode);
+        assert!(error
+            .to_string()
+            .contains("recursion limit of 100 exceeded"));
+    });
+}
+
+static FOO_ENTRY: MessageSerdeDecoderEntry =
+    MessageSerdeDecoderEntry::new("type.googleapis.com/any.test.Foo", |buf: &[u8]| {
+        let msg: Foo = ::prost::Message::decode(buf)?;
+        Ok(Box::new(msg))
+    })
+    .with_deserializer(|mut value| {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("@type");
+        }
+        let msg: Foo = serde::Deserialize::deserialize(value)?;
+        Ok(Box::new(msg))
+    });
+
+#[test]
+fn test_any_with_resolver() {
+    use prost_wkt::registry::Registry;
+    use prost_wkt::resolver::{with_resolver, WithResolver};
+
+    let foo = Foo {
+        string: String::from("scoped"),
+        ..Default::default()
+    };
+    let any = Any::try_pack(foo).unwrap();
+    let json = serde_json::to_value(&any).unwrap();
+
+    let accepting = Registry::new().with_entries(std::slice::from_ref(&FOO_ENTRY));
+    let back: Any = with_resolver(&accepting, || serde_json::from_value(json.clone())).unwrap();
+    assert_eq!(back, any);
+    assert_eq!(
+        serde_json::to_value(WithResolver(&accepting, &any)).unwrap(),
+        json
+    );
+
+    let rejecting = Registry::new();
+    let error =
+        with_resolver(&rejecting, || serde_json::from_value::<Any>(json.clone())).unwrap_err();
+    assert!(error.to_string().contains("not accepted by the resolver"));
+    let unresolved = serde_json::to_value(WithResolver(&rejecting, &any)).unwrap();
+    assert!(unresolved.get("string").is_none());
+    assert!(with_resolver(&rejecting, || any.try_unpack_ref()).is_err());
+}
+
+#[test]
+fn test_any_typed_helpers() {
+    let foo = Foo {
+        string: String::from("typed"),
+        ..Default::default()
+    };
+    let mut any = Any::pack_from(&foo).unwrap();
+    assert_eq!(any, Any::try_pack(foo.clone()).unwrap());
+    assert_eq!(any.type_name(), Some("any.test.Foo"));
+    assert!(any.is::<Foo>());
+    assert!(!any.is::<Duration>());
+
+    let mut back = Foo::default();
+    any.unpack_into(&mut back).unwrap();
+    assert_eq!(back, foo);
+
+    let mut duration = Duration::default();
+    let error = any.unpack_into(&mut duration).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to convert Value: expected type url type.googleapis.com/google.protobuf.Duration \
+         but got type.googleapis.com/any.test.Foo"
+    );
+    assert_eq!(duration, Duration::default());
+
+    any.type_url = "example.com/any.test.Foo".to_string();
+    assert!(any.is::<Foo>());
+    any.type_url = "any.test.Foo".to_string();
+    assert_eq!(any.type_name(), None);
+    assert!(!any.is::<Foo>());
+}
+
+#[test]
+fn test_boxed_message_defaults() {
+    // `Foo` relies on the default `clone_box` and `eq_dyn`, which go through the encoding.
+    let foo = Foo {
+        string: String::from("boxed"),
+        ..Default::default()
+    };
+    let boxed: Box<dyn MessageSerde> = Box::new(foo.clone());
+    let copy = boxed.clone();
+    assert!(*copy == *boxed);
+    assert_eq!(
+        format!("{copy:?}"),
+        r#"MessageSerde { type_url: "type.googleapis.com/any.test.Foo", .. }"#
+    );
+    assert!(copy.as_ref() != (&Duration::default() as &dyn MessageSerde));
+    assert_eq!(*copy.downcast::<Foo>().unwrap(), foo);
+}
+
+#[test]
+fn test_any_error_kinds() {
+    use std::error::Error;
+
+    let unknown = Any {
+        type_url: "type.googleapis.com/any.test.Unknown".to_string(),
+        value: vec![],
+    };
+    let error = unknown.try_unpack_ref().unwrap_err();
+    assert_eq!(error.kind(), AnyErrorKind::UnknownTypeUrl);
+    assert_eq!(
+        error.type_url(),
+        Some("type.googleapis.com/any.test.Unknown")
+    );
+    assert!(error.source().is_none());
+    assert_eq!(
+        error.to_string(),
+        "failed to convert Value: Failed to deserialize type.googleapis.com/any.test.Unknown. \
+         Make sure prost-wkt-build is executed."
+    );
+
+    let corrupt = Any {
+        type_url: "type.googleapis.com/any.test.Foo".to_string(),
+        value: vec![0x0a, 0x05, b'a'],
+    };
+    let error = corrupt.try_unpack_ref().unwrap_err();
+    assert_eq!(error.kind(), AnyErrorKind::Decode);
+    assert_eq!(error.type_url(), Some("type.googleapis.com/any.test.Foo"));
+    assert!(error
+        .source()
+        .unwrap()
+        .downcast_ref::<prost::DecodeError>()
+        .is_some());
+
+    let error = corrupt.unpack_into(&mut Duration::default()).unwrap_err();
+    assert_eq!(error.kind(), AnyErrorKind::TypeMismatch);
+    assert_eq!(error.type_url(), Some("type.googleapis.com/any.test.Foo"));
+
+    let error = corrupt.unpack_as(Foo::default()).unwrap_err();
+    assert_eq!(error.kind(), AnyErrorKind::Decode);
+    assert!(error
+        .to_string()
+        .starts_with("failed to convert Value: Error decoding message: "));
+
+    assert_eq!(AnyError::new("custom").kind(), AnyErrorKind::Other);
+}
@@ -57,3 +57,36 @@ fn test_flatten_list() {
 
     assert_eq!(string_list, string);
 }
+
+#[test]
+fn test_nesting_limit() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+    let value: Value = serde_json::from_str(&nested(50)).unwrap();
+    assert_eq!(serde_json::to_string(&value).unwrap(), nested(50));
+
+    let error = serde_json::from_str::<Value>(&nested(110)).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("recursion limit of 100 exceeded"));
+
+    let objects = format!("{}{}", r#"{"a":"#.repeat(110), "}".repeat(110)).replacen("}", "1}", 1);
+    let error = serde_json::from_str::<Struct>(&objects).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("recursion limit of 100 exceeded"));
+}
+
+#[test]
+fn test_value_error_kinds() {
+    let error = f64::try_from(Value::from(String::from("text"))).unwrap_err();
+    assert_eq!(error.kind(), ValueErrorKind::KindMismatch);
+    assert_eq!(
+        error.to_string(),
+        "failed to convert Value: Cannot convert to f64 because this is not a ValueNumber."
+    );
+
+    let error = String::try_from(Value::default()).unwrap_err();
+    assert_eq!(error.kind(), ValueErrorKind::Empty);
+    assert_eq!(ValueError::new("custom").kind(), ValueErrorKind::Other);
+}
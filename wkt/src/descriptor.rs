@@ -0,0 +1,122 @@
+//! Access to the protobuf descriptors that `prost-wkt-build` embeds in the generated code.
+//!
+//! With `SerdeOptions::with_file_descriptor_set` enabled, every generated package gets a
+//! `FILE_DESCRIPTOR_SET` static holding the encoded descriptors of its own proto files, and
+//! [`MessageSerde::descriptor`](crate::MessageSerde::descriptor) returns the descriptors of the
+//! message.
+
+use std::sync::OnceLock;
+
+use prost::Message;
+pub use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+
+/// An encoded `FileDescriptorSet` that is decoded on first use.
+pub struct EmbeddedFileDescriptorSet {
+    encoded: &'static [u8],
+    decoded: OnceLock<FileDescriptorSet>,
+}
+
+impl EmbeddedFileDescriptorSet {
+    /// Wraps the encoded descriptor set.
+    pub const fn new(encoded: &'static [u8]) -> Self {
+        Self {
+            encoded,
+            decoded: OnceLock::new(),
+        }
+    }
+
+    /// Returns the encoded descriptor set, e.g. to serve gRPC reflection.
+    pub fn encoded(&self) -> &'static [u8] {
+        self.encoded
+    }
+
+    /// Returns the decoded descriptor set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the embedded bytes are not a valid `FileDescriptorSet`, which does not happen
+    /// for the descriptors embedded by `prost-wkt-build`.
+    pub fn file_descriptor_set(&'static self) -> &'static FileDescriptorSet {
+        self.decoded.get_or_init(|| {
+            FileDescriptorSet::decode(self.encoded).expect("invalid embedded file descriptor set")
+        })
+    }
+
+    /// Returns the descriptor of the proto file with the given name, e.g. `my/pkg/foo.proto`.
+    pub fn file(&'static self, name: &str) -> Option<&'static FileDescriptorProto> {
+        self.file_descriptor_set()
+            .file
+            .iter()
+            .find(|file| file.name() == name)
+    }
+
+    /// Returns the descriptors of a message in the proto file with the given name. The message
+    /// path holds the names of the enclosing messages, outermost first, followed by the name of
+    /// the message itself.
+    pub fn message(&'static self, file: &str, message_path: &[&str]) -> Option<MessageDescriptor> {
+        let file = self.file(file)?;
+        let (first, nested) = message_path.split_first()?;
+        let mut message = file.message_type.iter().find(|msg| msg.name() == *first)?;
+        for name in nested {
+            message = message.nested_type.iter().find(|msg| msg.name() == *name)?;
+        }
+        Some(MessageDescriptor { file, message })
+    }
+}
+
+/// The descriptors of a message and the proto file it is declared in.
+#[derive(Clone, Copy, Debug)]
+pub struct MessageDescriptor {
+    file: &'static FileDescriptorProto,
+    message: &'static DescriptorProto,
+}
+
+impl MessageDescriptor {
+    /// The descriptor of the proto file the message is declared in.
+    pub fn file(&self) -> &'static FileDescriptorProto {
+        self.file
+    }
+
+    /// The descriptor of the message.
+    pub fn message(&self) -> &'static DescriptorProto {
+        self.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_nested_message() {
+        static SET: OnceLock<Vec<u8>> = OnceLock::new();
+        let encoded = SET.get_or_init(|| {
+            FileDescriptorSet {
+                file: vec![FileDescriptorProto {
+                    name: Some("my/pkg/foo.proto".to_string()),
+                    package: Some("my.pkg".to_string()),
+                    message_type: vec![DescriptorProto {
+                        name: Some("Outer".to_string()),
+                        nested_type: vec![DescriptorProto {
+                            name: Some("Inner".to_string()),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+            }
+            .encode_to_vec()
+        });
+        let embedded: &'static EmbeddedFileDescriptorSet =
+            Box::leak(Box::new(EmbeddedFileDescriptorSet::new(encoded)));
+
+        let descriptor = embedded
+            .message("my/pkg/foo.proto", &["Outer", "Inner"])
+            .unwrap();
+        assert_eq!(descriptor.message().name(), "Inner");
+        assert_eq!(descriptor.file().package(), "my.pkg");
+        assert!(embedded.message("my/pkg/foo.proto", &["Inner"]).is_none());
+        assert!(embedded.message("other.proto", &["Outer"]).is_none());
+    }
+}
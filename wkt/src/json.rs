@@ -0,0 +1,721 @@
+//! Support for the [proto3 JSON mapping](https://protobuf.dev/programming-guides/proto3/#json)
+//! used by the serde implementations that `prost-wkt-build` generates.
+//!
+//! Fields are (de)serialized through an encoding marker ([`Int32`], [`Int64`], [`Float`],
+//! [`Bytes`], [`Enum`], [`NullValue`] or [`Empty`]) that describes how the scalar values of the
+//! field are represented in JSON. The encoding applies to singular, optional, repeated and map
+//! value fields alike, and to the wrapper types like `google.protobuf.Int64Value` that prost maps
+//! to an `Option` of the wrapped scalar.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer, Unexpected, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+/// 32-bit integers, written as JSON numbers. Numbers in strings are accepted as well.
+pub struct Int32;
+
+/// 64-bit integers, written as JSON strings. Plain numbers are accepted as well.
+pub struct Int64;
+
+/// Floating point numbers, written as JSON numbers or as `"NaN"`, `"Infinity"` and `"-Infinity"`.
+pub struct Float;
+
+/// Bytes, written as standard base64 with padding. URL-safe and unpadded base64 is accepted as
+/// well.
+pub struct Bytes;
+
+/// Enums, written as the name of the enum value. Numbers are accepted as well, and values that
+/// are unknown to the enum are written as numbers so that they are preserved.
+pub struct Enum<T>(PhantomData<fn() -> T>);
+
+/// `google.protobuf.NullValue`, written as JSON `null`.
+pub struct NullValue;
+
+/// `google.protobuf.Empty`, which prost maps to `()`, written as an empty JSON object. Any object
+/// is accepted.
+pub struct Empty;
+
+/// The values of a proto enum, generated by `prost-wkt-build` from the enum descriptor.
+pub trait EnumValues {
+    /// The name and number of each enum value.
+    const VALUES: &'static [(&'static str, i32)];
+}
+
+/// A field type that can be (de)serialized with the proto3 JSON encoding `E`.
+pub trait ProtoJson<E>: Sized {
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Serializes the borrowed value with the proto3 JSON encoding `E`.
+pub struct SerializeAs<'a, E, T: ?Sized> {
+    value: &'a T,
+    encoding: PhantomData<fn() -> E>,
+}
+
+impl<'a, E, T: ?Sized> SerializeAs<'a, E, T> {
+    pub fn new(value: &'a T) -> Self {
+        SerializeAs {
+            value,
+            encoding: PhantomData,
+        }
+    }
+}
+
+impl<E, T> Serialize for SerializeAs<'_, E, T>
+where
+    T: ProtoJson<E>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize_json(serializer)
+    }
+}
+
+/// Deserializes a value with the proto3 JSON encoding `E`.
+pub struct DeserializeAs<E, T> {
+    value: T,
+    encoding: PhantomData<fn() -> E>,
+}
+
+impl<E, T> DeserializeAs<E, T> {
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<'de, E, T> Deserialize<'de> for DeserializeAs<E, T>
+where
+    T: ProtoJson<E>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize_json(deserializer).map(|value| DeserializeAs {
+            value,
+            encoding: PhantomData,
+        })
+    }
+}
+
+/// Deserializes a value, treating JSON `null` as the default value of the field.
+pub struct OrDefault<T>(T);
+
+impl<T> OrDefault<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<'de, T> Deserialize<'de> for OrDefault<T>
+where
+    T: Deserialize<'de> + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<T>::deserialize(deserializer).map(|value| OrDefault(value.unwrap_or_default()))
+    }
+}
+
+/// Returns `true` if the field holds its default value, in which case it is omitted from the
+/// JSON output.
+pub fn is_default<T>(value: &T) -> bool
+where
+    T: Default + PartialEq,
+{
+    *value == T::default()
+}
+
+struct IntegerVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for IntegerVisitor<T>
+where
+    T: TryFrom<i128> + Default,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer or a string containing an integer")
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::try_from(value as i128).map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::try_from(value as i128).map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if value.fract() != 0.0 || value.abs() > u64::MAX as f64 {
+            return Err(E::invalid_value(Unexpected::Float(value), &self));
+        }
+        T::try_from(value as i128).map_err(|_| E::invalid_value(Unexpected::Float(value), &self))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        value
+            .parse::<i128>()
+            .ok()
+            .and_then(|value| T::try_from(value).ok())
+            .ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::default())
+    }
+}
+
+macro_rules! impl_integer {
+    ($encoding:ty, $ty:ty, $as_string:expr) => {
+        impl ProtoJson<$encoding> for $ty {
+            fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                if $as_string {
+                    serializer.collect_str(self)
+                } else {
+                    self.serialize(serializer)
+                }
+            }
+
+            fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_any(IntegerVisitor(PhantomData))
+            }
+        }
+    };
+}
+
+impl_integer!(Int32, i32, false);
+impl_integer!(Int32, u32, false);
+impl_integer!(Int64, i64, true);
+impl_integer!(Int64, u64, true);
+
+struct FloatVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for FloatVisitor<T>
+where
+    T: From<f32> + Default + JsonFloat,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number, \"NaN\", \"Infinity\" or \"-Infinity\"")
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::from_f64(value as f64))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::from_f64(value as f64))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::from_f64(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match value {
+            "NaN" => Ok(T::from(f32::NAN)),
+            "Infinity" => Ok(T::from(f32::INFINITY)),
+            "-Infinity" => Ok(T::from(f32::NEG_INFINITY)),
+            _ => value
+                .parse::<f64>()
+                .map(T::from_f64)
+                .map_err(|_| E::invalid_value(Unexpected::Str(value), &self)),
+        }
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::default())
+    }
+}
+
+trait JsonFloat {
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_float {
+    ($ty:ty) => {
+        impl JsonFloat for $ty {
+            fn from_f64(value: f64) -> Self {
+                value as $ty
+            }
+        }
+
+        impl ProtoJson<Float> for $ty {
+            fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                if self.is_nan() {
+                    serializer.serialize_str("NaN")
+                } else if self.is_infinite() && self.is_sign_positive() {
+                    serializer.serialize_str("Infinity")
+                } else if self.is_infinite() {
+                    serializer.serialize_str("-Infinity")
+                } else {
+                    self.serialize(serializer)
+                }
+            }
+
+            fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_any(FloatVisitor(PhantomData))
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Encodes the bytes as standard base64 with padding.
+pub fn encode_base64(bytes: &[u8]) -> String {
+    BASE64.encode(bytes)
+}
+
+/// Decodes standard or URL-safe base64, with or without padding.
+pub fn decode_base64(value: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    if value.contains(['-', '_']) {
+        BASE64.decode(value.replace('-', "+").replace('_', "/"))
+    } else {
+        BASE64.decode(value)
+    }
+}
+
+struct BytesVisitor;
+
+impl Visitor<'_> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a base64 encoded string")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        decode_base64(value).map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Vec::new())
+    }
+}
+
+impl ProtoJson<Bytes> for Vec<u8> {
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode_base64(self))
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(BytesVisitor)
+    }
+}
+
+impl ProtoJson<Bytes> for prost::bytes::Bytes {
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode_base64(self))
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_any(BytesVisitor)
+            .map(prost::bytes::Bytes::from)
+    }
+}
+
+struct EnumVisitor<T>(PhantomData<T>);
+
+impl<T> Visitor<'_> for EnumVisitor<T>
+where
+    T: EnumValues,
+{
+    type Value = i32;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an enum value name or number")
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        i32::try_from(value).map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        i32::try_from(value).map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        T::VALUES
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, number)| *number)
+            .ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(0)
+    }
+}
+
+impl<T> ProtoJson<Enum<T>> for i32
+where
+    T: EnumValues,
+{
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match T::VALUES.iter().find(|(_, number)| number == self) {
+            Some((name, _)) => serializer.serialize_str(name),
+            None => serializer.serialize_i32(*self),
+        }
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(EnumVisitor::<T>(PhantomData))
+    }
+}
+
+struct NullValueEnum;
+
+impl EnumValues for NullValueEnum {
+    const VALUES: &'static [(&'static str, i32)] = &[("NULL_VALUE", 0)];
+}
+
+impl ProtoJson<NullValue> for i32 {
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_unit()
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(EnumVisitor::<NullValueEnum>(PhantomData))
+    }
+}
+
+struct EmptyVisitor;
+
+impl<'de> Visitor<'de> for EmptyVisitor {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        while map
+            .next_entry::<de::IgnoredAny, de::IgnoredAny>()?
+            .is_some()
+        {}
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+}
+
+impl ProtoJson<Empty> for () {
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_map(Some(0))?.end()
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(EmptyVisitor)
+    }
+}
+
+impl<E, T> ProtoJson<E> for Option<T>
+where
+    T: ProtoJson<E>,
+{
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Some(value) => value.serialize_json(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<DeserializeAs<E, T>>::deserialize(deserializer)
+            .map(|value| value.map(DeserializeAs::into_inner))
+    }
+}
+
+impl<E, T> ProtoJson<E> for Vec<T>
+where
+    T: ProtoJson<E>,
+{
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for value in self {
+            seq.serialize_element(&SerializeAs::<E, T>::new(value))?;
+        }
+        seq.end()
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<Vec<DeserializeAs<E, T>>>::deserialize(deserializer).map(|values| {
+            values
+                .unwrap_or_default()
+                .into_iter()
+                .map(DeserializeAs::into_inner)
+                .collect()
+        })
+    }
+}
+
+impl<E, K, V, H> ProtoJson<E> for HashMap<K, V, H>
+where
+    K: Serialize + DeserializeOwned + Eq + Hash,
+    V: ProtoJson<E>,
+    H: std::hash::BuildHasher + Default,
+{
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key, &SerializeAs::<E, V>::new(value))?;
+        }
+        map.end()
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<HashMap<K, DeserializeAs<E, V>>>::deserialize(deserializer).map(|values| {
+            values
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, value)| (key, value.into_inner()))
+                .collect()
+        })
+    }
+}
+
+impl<E, K, V> ProtoJson<E> for BTreeMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Ord,
+    V: ProtoJson<E>,
+{
+    fn serialize_json<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key, &SerializeAs::<E, V>::new(value))?;
+        }
+        map.end()
+    }
+
+    fn deserialize_json<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<BTreeMap<K, DeserializeAs<E, V>>>::deserialize(deserializer).map(|values| {
+            values
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, value)| (key, value.into_inner()))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int64_as_string() {
+        let value: i64 = -42;
+        let json = serde_json::to_string(&SerializeAs::<Int64, _>::new(&value)).unwrap();
+        assert_eq!(json, r#""-42""#);
+
+        let back: DeserializeAs<Int64, i64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.into_inner(), value);
+        let back: DeserializeAs<Int64, u64> = serde_json::from_str("18446744073709551615").unwrap();
+        assert_eq!(back.into_inner(), u64::MAX);
+        assert!(serde_json::from_str::<DeserializeAs<Int32, u32>>("-1").is_err());
+    }
+
+    #[test]
+    fn float_special_values() {
+        let values = vec![1.5f64, f64::INFINITY, f64::NEG_INFINITY];
+        let json = serde_json::to_string(&SerializeAs::<Float, _>::new(&values)).unwrap();
+        assert_eq!(json, r#"[1.5,"Infinity","-Infinity"]"#);
+
+        let back: DeserializeAs<Float, Vec<f64>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.into_inner(), values);
+        let nan: DeserializeAs<Float, f32> = serde_json::from_str(r#""NaN""#).unwrap();
+        assert!(nan.into_inner().is_nan());
+    }
+
+    struct Status;
+
+    impl EnumValues for Status {
+        const VALUES: &'static [(&'static str, i32)] =
+            &[("STATUS_UNSPECIFIED", 0), ("STATUS_ACTIVE", 1)];
+    }
+
+    #[test]
+    fn enum_as_name() {
+        let values = vec![1, 7];
+        let json = serde_json::to_string(&SerializeAs::<Enum<Status>, _>::new(&values)).unwrap();
+        assert_eq!(json, r#"["STATUS_ACTIVE",7]"#);
+
+        let back: DeserializeAs<Enum<Status>, Vec<i32>> =
+            serde_json::from_str(r#"["STATUS_ACTIVE",1,7]"#).unwrap();
+        assert_eq!(back.into_inner(), vec![1, 1, 7]);
+        assert!(serde_json::from_str::<DeserializeAs<Enum<Status>, i32>>(r#""UNKNOWN""#).is_err());
+    }
+
+    #[test]
+    fn bytes_as_base64() {
+        let mut values: HashMap<String, Vec<u8>> = HashMap::new();
+        values.insert("key".to_string(), vec![0xfb, 0xff]);
+        let json = serde_json::to_string(&SerializeAs::<Bytes, _>::new(&values)).unwrap();
+        assert_eq!(json, r#"{"key":"+/8="}"#);
+
+        let back: DeserializeAs<Bytes, HashMap<String, Vec<u8>>> =
+            serde_json::from_str(r#"{"key":"-_8"}"#).unwrap();
+        assert_eq!(back.into_inner(), values);
+    }
+
+    #[test]
+    fn empty_as_object() {
+        let json = serde_json::to_string(&SerializeAs::<Empty, _>::new(&Some(()))).unwrap();
+        assert_eq!(json, "{}");
+
+        let back: DeserializeAs<Empty, Option<()>> = serde_json::from_str("{}").unwrap();
+        assert_eq!(back.into_inner(), Some(()));
+        let back: DeserializeAs<Empty, Option<()>> = serde_json::from_str("null").unwrap();
+        assert_eq!(back.into_inner(), None);
+    }
+}
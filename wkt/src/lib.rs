@@ -2,6 +2,20 @@ pub use inventory;
 
 pub use typetag;
 
+pub use serde;
+
+pub mod json;
+
+pub mod descriptor;
+
+pub mod registry;
+
+pub mod resolver;
+
+pub mod recursion;
+
+use crate::descriptor::MessageDescriptor;
+
 /// Trait to support serialization and deserialization of `prost` messages.
 #[typetag::serde(tag = "@type")]
 pub trait MessageSerde: prost::Message + std::any::Any {
@@ -15,6 +29,85 @@ pub trait MessageSerde: prost::Message + std::any::Any {
     fn new_instance(&self, data: Vec<u8>) -> Result<Box<dyn MessageSerde>, prost::DecodeError>;
     /// Returns the encoded protobuf message as bytes
     fn try_encoded(&self) -> Result<Vec<u8>, prost::EncodeError>;
+    /// Returns the descriptors of the message and its proto file, if they were embedded in the
+    /// generated code
+    fn descriptor(&self) -> Option<MessageDescriptor> {
+        None
+    }
+    /// Returns a boxed copy of the message. The generated code clones the message, by default a
+    /// copy is decoded from the encoded message
+    fn clone_box(&self) -> Box<dyn MessageSerde> {
+        let encoded = self.try_encoded().expect("encoding into a Vec never fails");
+        self.new_instance(encoded)
+            .expect("decoding an encoded message never fails")
+    }
+    /// Returns `true` if the other message is of the same type and equal to this one. The
+    /// generated code compares the messages with `PartialEq`, by default the type urls and
+    /// encoded messages are compared
+    fn eq_dyn(&self, other: &dyn MessageSerde) -> bool {
+        self.type_url() == other.type_url()
+            && matches!((self.try_encoded(), other.try_encoded()), (Ok(a), Ok(b)) if a == b)
+    }
+    /// Formats the message for debugging. The generated code uses the `Debug` implementation of
+    /// the message if it has one, by default only the type url is shown
+    fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_type_url(self.type_url(), f)
+    }
+}
+
+impl std::fmt::Debug for dyn MessageSerde {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_debug(f)
+    }
+}
+
+fn fmt_type_url(type_url: &str, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MessageSerde")
+        .field("type_url", &type_url)
+        .finish_non_exhaustive()
+}
+
+/// Formats a message with its `Debug` implementation, or with its type url if it has none, e.g.
+/// when prost's `skip_debug` is used. Used by the generated `MessageSerde::fmt_debug`:
+/// `(&DebugMessage(self)).fmt_message(f)` picks [`FmtDebug`] if the message implements `Debug`
+/// and falls back to [`FmtTypeUrl`] otherwise.
+#[doc(hidden)]
+pub struct DebugMessage<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait FmtDebug {
+    fn fmt_message(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+}
+
+impl<T: std::fmt::Debug> FmtDebug for DebugMessage<'_, T> {
+    fn fmt_message(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.0, f)
+    }
+}
+
+#[doc(hidden)]
+pub trait FmtTypeUrl {
+    fn fmt_message(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+}
+
+impl<T: MessageSerde> FmtTypeUrl for &DebugMessage<'_, T> {
+    fn fmt_message(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_type_url(self.0.type_url(), f)
+    }
+}
+
+impl Clone for Box<dyn MessageSerde> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Compares messages with [`MessageSerde::eq_dyn`]. Compare boxed messages through their contents,
+/// e.g. `*a == *b`, as `a == b` tries to move the boxes.
+impl PartialEq for dyn MessageSerde {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_dyn(other)
+    }
 }
 
 /// The implementation here is a direct copy of the `impl dyn` of [`std::any::Any`]!
@@ -32,6 +125,18 @@ impl dyn MessageSerde {
         t == concrete
     }
 
+    /// Returns the boxed value if it is of type `T`, or gives the box back if it isn't.
+    #[inline]
+    pub fn downcast<T: MessageSerde>(self: Box<Self>) -> Result<Box<T>, Box<dyn MessageSerde>> {
+        if self.is::<T>() {
+            // SAFETY: just checked whether we are pointing to the correct type, so the allocation
+            // holds a `T`.
+            unsafe { Ok(Box::from_raw(Box::into_raw(self) as *mut T)) }
+        } else {
+            Err(self)
+        }
+    }
+
     /// Returns some reference to the inner value if it is of type `T`, or
     /// `None` if it isn't.
     #[inline]
@@ -87,9 +192,71 @@ impl dyn MessageSerde {
 
 type MessageSerdeDecoderFn = fn(&[u8]) -> Result<Box<dyn MessageSerde>, ::prost::DecodeError>;
 
+type MessageSerdeDeserializerFn =
+    fn(serde_json::Value) -> Result<Box<dyn MessageSerde>, serde_json::Error>;
+
+type MessageDescriptorFn = fn() -> Option<MessageDescriptor>;
+
+/// The registration of a message type for `Any`, collected with `inventory` or added to a
+/// [`Registry`](crate::registry::Registry).
+///
+/// Create entries with [`MessageSerdeDecoderEntry::new`], so that they keep compiling when fields
+/// are added:
+///
+/// ```rust,ignore
+/// prost_wkt::inventory::submit! {
+///     prost_wkt::MessageSerdeDecoderEntry::new("type.googleapis.com/my.pkg.Foo", |buf| {
+///         let msg: Foo = prost::Message::decode(buf)?;
+///         Ok(Box::new(msg))
+///     })
+/// }
+/// ```
 pub struct MessageSerdeDecoderEntry {
     pub type_url: &'static str,
     pub decoder: MessageSerdeDecoderFn,
+    deserializer: MessageSerdeDeserializerFn,
+    descriptor: MessageDescriptorFn,
+}
+
+impl MessageSerdeDecoderEntry {
+    /// Creates an entry for the type url with the given decoder. The message is deserialized
+    /// from JSON through its `typetag` registration and has no descriptors, see
+    /// [`with_deserializer`](Self::with_deserializer) and
+    /// [`with_descriptor`](Self::with_descriptor).
+    pub const fn new(type_url: &'static str, decoder: MessageSerdeDecoderFn) -> Self {
+        Self {
+            type_url,
+            decoder,
+            deserializer: |value| <Box<dyn MessageSerde> as serde::Deserialize>::deserialize(value),
+            descriptor: || None,
+        }
+    }
+
+    /// Sets the function that deserializes the message from its JSON representation.
+    pub const fn with_deserializer(self, deserializer: MessageSerdeDeserializerFn) -> Self {
+        Self {
+            deserializer,
+            ..self
+        }
+    }
+
+    /// Sets the function that returns the descriptors of the message.
+    pub const fn with_descriptor(self, descriptor: MessageDescriptorFn) -> Self {
+        Self { descriptor, ..self }
+    }
+
+    /// Deserializes the message from its JSON representation, including the `@type` field.
+    pub fn deserialize(
+        &self,
+        value: serde_json::Value,
+    ) -> Result<Box<dyn MessageSerde>, serde_json::Error> {
+        (self.deserializer)(value)
+    }
+
+    /// Returns the descriptors of the message, see [`MessageSerde::descriptor`].
+    pub fn descriptor(&self) -> Option<MessageDescriptor> {
+        (self.descriptor)()
+    }
 }
 
 inventory::collect!(MessageSerdeDecoderEntry);
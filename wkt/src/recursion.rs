@@ -0,0 +1,98 @@
+//! A limit on how deeply `Any`, `Struct`, `Value` and `ListValue` can be nested.
+//!
+//! These types can contain themselves, so (de)serializing them recurses once per level. Each
+//! level [`enter`]s the limit while it is being processed, and fails with
+//! [`RecursionLimitExceeded`] instead of overflowing the stack on untrusted input like
+//! `[[[[...]]]]` or deeply nested `@type` objects. The depth is counted per thread.
+
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The default nesting limit, the same as the recursion limit of `prost` decoding.
+pub const DEFAULT_RECURSION_LIMIT: usize = 100;
+
+static RECURSION_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_RECURSION_LIMIT);
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Sets how many levels of `Any`, `Struct`, `Value` and `ListValue` can be nested.
+pub fn set_recursion_limit(limit: usize) {
+    RECURSION_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// Returns the nesting limit, see [`set_recursion_limit`].
+pub fn recursion_limit() -> usize {
+    RECURSION_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Enters one level of nesting, which is left again when the returned guard is dropped. Fails
+/// if the limit is exceeded.
+pub fn enter() -> Result<DepthGuard, RecursionLimitExceeded> {
+    let limit = recursion_limit();
+    DEPTH.with(|depth| {
+        if depth.get() >= limit {
+            return Err(RecursionLimitExceeded { limit });
+        }
+        depth.set(depth.get() + 1);
+        Ok(DepthGuard {
+            not_send: PhantomData,
+        })
+    })
+}
+
+/// One level of nesting, see [`enter`].
+#[must_use]
+pub struct DepthGuard {
+    // The depth is counted per thread, so the guard must be dropped on the thread it was
+    // created on.
+    not_send: PhantomData<*const ()>,
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// The error returned when the nesting limit is exceeded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecursionLimitExceeded {
+    limit: usize,
+}
+
+impl RecursionLimitExceeded {
+    /// The limit that was exceeded.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for RecursionLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "recursion limit of {} exceeded", self.limit)
+    }
+}
+
+impl std::error::Error for RecursionLimitExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enter_up_to_the_limit() {
+        let guards = (0..DEFAULT_RECURSION_LIMIT)
+            .map(|_| enter().unwrap())
+            .collect::<Vec<_>>();
+        let error = enter().err().unwrap();
+        assert_eq!(error.limit(), DEFAULT_RECURSION_LIMIT);
+        assert_eq!(error.to_string(), "recursion limit of 100 exceeded");
+
+        drop(guards);
+        assert!(enter().is_ok());
+    }
+}
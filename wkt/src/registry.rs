@@ -0,0 +1,369 @@
+//! Lookup and registration of the message types that can be packed into an `Any`.
+//!
+//! Message types are found in three places, in this order:
+//!
+//! 1. the [`Registry`] installed with [`install`] (requires the `static-registry` feature),
+//! 2. the entries added at runtime with [`register`], e.g. for plugin-loaded or test-only types,
+//! 3. the entries the generated code submits through `inventory`.
+//!
+//! `inventory` relies on constructors that run before `main`. Those are not available on every
+//! target (e.g. `wasm32-unknown-unknown`) and get lost when the linker drops the objects of a
+//! static library. With the `static-registry` feature a [`Registry`] can be installed instead,
+//! built from the `MESSAGE_SERDE_ENTRIES` tables that `prost-wkt-build` generates per package when
+//! `SerdeOptions::with_static_registry` is enabled.
+//!
+//! ```rust,ignore
+//! let registry = prost_wkt::registry::Registry::new()
+//!     .with_entries(my::pkg::MESSAGE_SERDE_ENTRIES)
+//!     .with_entries(my::other::MESSAGE_SERDE_ENTRIES);
+//! prost_wkt::registry::install(registry).expect("registry is installed once");
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, RwLock};
+
+use crate::MessageSerdeDecoderEntry;
+
+#[cfg(feature = "static-registry")]
+use std::sync::OnceLock;
+
+/// A set of message types by type url.
+#[derive(Default)]
+pub struct Registry {
+    entries: Vec<&'static MessageSerdeDecoderEntry>,
+    // The first entry for each type url and fully qualified name, so that lookups do not scan.
+    by_type_url: HashMap<&'static str, &'static MessageSerdeDecoderEntry>,
+    by_full_name: HashMap<&'static str, &'static MessageSerdeDecoderEntry>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the entries of a generated `MESSAGE_SERDE_ENTRIES` table.
+    pub fn with_entries(mut self, entries: &'static [MessageSerdeDecoderEntry]) -> Self {
+        self.add_entries(entries);
+        self
+    }
+
+    /// Adds the entries of a generated `MESSAGE_SERDE_ENTRIES` table. Entries with a type url
+    /// that is already in the registry are added as well, see [`duplicates`](Self::duplicates).
+    pub fn add_entries(&mut self, entries: &'static [MessageSerdeDecoderEntry]) {
+        for entry in entries {
+            self.push(entry);
+        }
+    }
+
+    /// Adds a single entry, unless an entry with the same type url is already in the registry.
+    pub fn register(
+        &mut self,
+        entry: &'static MessageSerdeDecoderEntry,
+    ) -> Result<(), DuplicateTypeUrl> {
+        if self.get(entry.type_url).is_some() {
+            return Err(DuplicateTypeUrl::new(entry.type_url));
+        }
+        self.push(entry);
+        Ok(())
+    }
+
+    fn push(&mut self, entry: &'static MessageSerdeDecoderEntry) {
+        self.entries.push(entry);
+        self.by_type_url.entry(entry.type_url).or_insert(entry);
+        self.by_full_name
+            .entry(full_name_of(entry.type_url))
+            .or_insert(entry);
+    }
+
+    /// Returns the entry for the given type url.
+    pub fn get(&self, type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+        self.by_type_url.get(type_url).copied()
+    }
+
+    /// Returns the entry for the given fully qualified message name, e.g. `my.pkg.Message`.
+    pub fn get_by_full_name(&self, full_name: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+        self.by_full_name.get(full_name).copied()
+    }
+
+    /// Returns the entry for the type of the given type url, like the global [`resolve`].
+    pub fn resolve(&self, type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+        self.get(type_url)
+            .or_else(|| self.get_by_full_name(resolvable_full_name(type_url)?))
+    }
+
+    /// Returns all entries in the order they were added.
+    pub fn entries(&self) -> impl Iterator<Item = &'static MessageSerdeDecoderEntry> + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// Returns the type urls that were added more than once.
+    pub fn duplicates(&self) -> Vec<&'static str> {
+        duplicate_type_urls(self.entries())
+    }
+}
+
+/// The error returned when registering a type url that is already registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateTypeUrl {
+    type_url: &'static str,
+}
+
+impl DuplicateTypeUrl {
+    fn new(type_url: &'static str) -> Self {
+        Self { type_url }
+    }
+
+    /// The type url that was registered twice.
+    pub fn type_url(&self) -> &'static str {
+        self.type_url
+    }
+}
+
+impl fmt::Display for DuplicateTypeUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "type url `{}` is already registered", self.type_url)
+    }
+}
+
+impl std::error::Error for DuplicateTypeUrl {}
+
+#[cfg(feature = "static-registry")]
+static INSTALLED: OnceLock<Registry> = OnceLock::new();
+
+static RUNTIME: LazyLock<RwLock<Registry>> = LazyLock::new(Default::default);
+
+// `inventory` entries are all submitted before `main`, so they are indexed once on first use.
+static INVENTORY: LazyLock<Registry> = LazyLock::new(|| {
+    let mut registry = Registry::new();
+    for entry in inventory::iter::<MessageSerdeDecoderEntry> {
+        registry.push(entry);
+    }
+    registry
+});
+
+/// Installs the registry that is consulted before the runtime and `inventory` registrations. The
+/// registry can only be installed once; a second call returns the given registry as error.
+#[cfg(feature = "static-registry")]
+pub fn install(registry: Registry) -> Result<(), Registry> {
+    INSTALLED.set(registry)
+}
+
+/// Returns the installed registry, if any.
+#[cfg(feature = "static-registry")]
+pub fn installed() -> Option<&'static Registry> {
+    INSTALLED.get()
+}
+
+/// Registers a message type at runtime. Fails if the type url is already registered, either at
+/// runtime, in the installed registry or through `inventory`.
+pub fn register(entry: MessageSerdeDecoderEntry) -> Result<(), DuplicateTypeUrl> {
+    // Hold the lock while checking, so that concurrent registrations cannot both succeed.
+    let mut runtime = RUNTIME.write().unwrap_or_else(|error| error.into_inner());
+    let registered = installed_registry()
+        .and_then(|registry| registry.get(entry.type_url))
+        .or_else(|| runtime.get(entry.type_url))
+        .or_else(|| INVENTORY.get(entry.type_url))
+        .is_some();
+    if registered {
+        return Err(DuplicateTypeUrl::new(entry.type_url));
+    }
+    runtime.push(Box::leak(Box::new(entry)));
+    Ok(())
+}
+
+/// Returns the entry for the given type url.
+pub fn find(type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+    lookup(|registry| registry.get(type_url))
+}
+
+/// Returns the entry for the given fully qualified message name, e.g. `my.pkg.Message`,
+/// regardless of the domain of its type url.
+pub fn find_by_full_name(full_name: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+    lookup(|registry| registry.get_by_full_name(full_name))
+}
+
+// Looks an entry up in the installed, runtime and `inventory` registrations, in that order.
+fn lookup(
+    get: impl Fn(&Registry) -> Option<&'static MessageSerdeDecoderEntry>,
+) -> Option<&'static MessageSerdeDecoderEntry> {
+    if let Some(entry) = installed_registry().and_then(&get) {
+        return Some(entry);
+    }
+    let runtime = RUNTIME.read().unwrap_or_else(|error| error.into_inner());
+    get(&runtime).or_else(|| get(&INVENTORY))
+}
+
+/// Returns the entry for the type of the given type url, as `Any` resolves it.
+///
+/// Only the fully qualified name after the last `/` identifies the type, so
+/// `example.com/my.pkg.Message` resolves to the entry registered as
+/// `type.googleapis.com/my.pkg.Message`. An entry with exactly the given type url is preferred.
+/// With [`set_strict_type_urls`] enabled only exact matches are returned, like [`find`].
+pub fn resolve(type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+    find(type_url).or_else(|| find_by_full_name(resolvable_full_name(type_url)?))
+}
+
+// The full name a type url resolves to when there is no exact match, see `resolve`.
+fn resolvable_full_name(type_url: &str) -> Option<&str> {
+    if strict_type_urls() {
+        return None;
+    }
+    match type_url.rfind('/') {
+        Some(pos) if !type_url[pos + 1..].starts_with('.') => Some(&type_url[pos + 1..]),
+        _ => None,
+    }
+}
+
+static STRICT_TYPE_URLS: AtomicBool = AtomicBool::new(false);
+
+/// Makes [`resolve`], and with it `Any`, only accept type urls that match a registration
+/// exactly. Disabled by default.
+///
+/// This applies to the whole process. To only accept exact type urls in some calls, put a
+/// [`StrictTypeUrls`](crate::resolver::StrictTypeUrls) resolver in place for them instead.
+pub fn set_strict_type_urls(enabled: bool) {
+    STRICT_TYPE_URLS.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether only exactly matching type urls are resolved, see [`set_strict_type_urls`].
+pub fn strict_type_urls() -> bool {
+    STRICT_TYPE_URLS.load(Ordering::Relaxed)
+}
+
+/// Returns all registered entries in lookup order. A type url that is registered more than once
+/// is listed more than once, and the first entry is the one that is used.
+pub fn entries() -> impl Iterator<Item = &'static MessageSerdeDecoderEntry> {
+    let runtime = RUNTIME
+        .read()
+        .unwrap_or_else(|error| error.into_inner())
+        .entries
+        .clone();
+
+    installed_registry()
+        .into_iter()
+        .flat_map(Registry::entries)
+        .chain(runtime)
+        .chain(INVENTORY.entries())
+}
+
+fn installed_registry() -> Option<&'static Registry> {
+    #[cfg(feature = "static-registry")]
+    return installed();
+    #[cfg(not(feature = "static-registry"))]
+    None
+}
+
+/// Returns the type urls that are registered more than once, e.g. because two crates generated
+/// code for the same message. Lookups use the first registration, see [`entries`].
+pub fn duplicates() -> Vec<&'static str> {
+    duplicate_type_urls(entries())
+}
+
+fn duplicate_type_urls(
+    entries: impl Iterator<Item = &'static MessageSerdeDecoderEntry>,
+) -> Vec<&'static str> {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    for entry in entries {
+        if !seen.insert(entry.type_url) && !duplicates.contains(&entry.type_url) {
+            duplicates.push(entry.type_url);
+        }
+    }
+    duplicates
+}
+
+// The fully qualified message name is the part of the type url after the last `/`.
+fn full_name_of(type_url: &str) -> &str {
+    match type_url.rfind('/') {
+        Some(pos) => &type_url[pos + 1..],
+        None => type_url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::{GlobalRegistry, StrictTypeUrls, TypeResolver};
+
+    #[allow(deprecated)]
+    fn entry(type_url: &'static str) -> MessageSerdeDecoderEntry {
+        MessageSerdeDecoderEntry::new(type_url, |_| Err(prost::DecodeError::new("not decodable")))
+    }
+
+    fn leak(entry: MessageSerdeDecoderEntry) -> &'static MessageSerdeDecoderEntry {
+        Box::leak(Box::new(entry))
+    }
+
+    #[test]
+    fn register_at_runtime() {
+        let type_url = "type.googleapis.com/registry.test.Runtime";
+        assert!(find(type_url).is_none());
+
+        register(entry(type_url)).unwrap();
+        assert_eq!(find(type_url).unwrap().type_url, type_url);
+        assert_eq!(
+            find_by_full_name("registry.test.Runtime").unwrap().type_url,
+            type_url
+        );
+        assert!(entries().any(|entry| entry.type_url == type_url));
+
+        let error = register(entry(type_url)).unwrap_err();
+        assert_eq!(error.type_url(), type_url);
+        assert_eq!(
+            error.to_string(),
+            "type url `type.googleapis.com/registry.test.Runtime` is already registered"
+        );
+        assert!(!duplicates().contains(&type_url));
+    }
+
+    #[test]
+    fn report_duplicates_in_registry() {
+        let mut registry = Registry::new();
+        registry
+            .register(leak(entry("example.com/registry.test.A")))
+            .unwrap();
+        assert!(registry
+            .register(leak(entry("example.com/registry.test.A")))
+            .is_err());
+        assert!(registry.duplicates().is_empty());
+
+        let table: &'static [MessageSerdeDecoderEntry] = Box::leak(Box::new([
+            entry("other.com/registry.test.B"),
+            entry("example.com/registry.test.A"),
+        ]));
+        registry.add_entries(table);
+        assert_eq!(registry.duplicates(), vec!["example.com/registry.test.A"]);
+        assert_eq!(
+            registry
+                .get_by_full_name("registry.test.B")
+                .map(|entry| entry.type_url),
+            Some("other.com/registry.test.B")
+        );
+        assert_eq!(registry.entries().count(), 3);
+    }
+
+    #[test]
+    fn resolve_by_full_name() {
+        let type_url = "type.googleapis.com/registry.test.Resolved";
+        register(entry(type_url)).unwrap();
+
+        assert_eq!(resolve(type_url).unwrap().type_url, type_url);
+        assert_eq!(
+            resolve("example.com/registry.test.Resolved")
+                .unwrap()
+                .type_url,
+            type_url
+        );
+        assert!(resolve("example.com/.registry.test.Resolved").is_none());
+        assert!(resolve("registry.test.Resolved").is_none());
+
+        let strict = StrictTypeUrls(GlobalRegistry);
+        assert!(strict
+            .resolve("example.com/registry.test.Resolved")
+            .is_none());
+        assert!(strict.resolve(type_url).is_some());
+    }
+}
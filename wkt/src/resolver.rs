@@ -0,0 +1,219 @@
+//! Choosing which message types `Any` can hold during (de)serialization.
+//!
+//! By default `Any` resolves its type url through the global [`registry`](crate::registry). A
+//! [`TypeResolver`] can be put in place for the duration of a call, to restrict the types an
+//! endpoint accepts or to resolve fake types in tests:
+//!
+//! ```rust,ignore
+//! let registry = prost_wkt::registry::Registry::new().with_entries(my::pkg::MESSAGE_SERDE_ENTRIES);
+//!
+//! let json = serde_json::to_string(&WithResolver(&registry, &msg))?;
+//! let msg: MyMessage = with_resolver(&registry, || serde_json::from_str(&json))?;
+//! ```
+//!
+//! While a resolver is in place, `Any` only deserializes the types it resolves and rejects all
+//! others. The well-known types are always handled by `Any` itself.
+
+use std::cell::Cell;
+use std::ptr::NonNull;
+
+use serde::ser::{Serialize, Serializer};
+
+use crate::registry::{self, Registry};
+use crate::MessageSerdeDecoderEntry;
+
+/// Finds the message type for the type url of an `Any`.
+pub trait TypeResolver {
+    /// Returns the entry for the type of the given type url, or `None` if the type is unknown or
+    /// not accepted.
+    fn resolve(&self, type_url: &str) -> Option<&'static MessageSerdeDecoderEntry>;
+
+    /// Returns whether only exact type urls are accepted, which `Any` also applies to the
+    /// well-known types it handles itself. Defaults to `false`.
+    fn strict_type_urls(&self) -> bool {
+        false
+    }
+}
+
+/// The default resolver, which uses the global [`registry`](crate::registry).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GlobalRegistry;
+
+impl TypeResolver for GlobalRegistry {
+    fn resolve(&self, type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+        registry::resolve(type_url)
+    }
+}
+
+impl TypeResolver for Registry {
+    fn resolve(&self, type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+        Registry::resolve(self, type_url)
+    }
+}
+
+/// Only resolves the type urls that match an entry of the wrapped resolver exactly, so that e.g.
+/// `example.com/my.pkg.Message` is not accepted for `type.googleapis.com/my.pkg.Message`.
+///
+/// Unlike [`registry::set_strict_type_urls`] this only applies to the calls the resolver is put
+/// in place for:
+///
+/// ```rust,ignore
+/// let msg: MyMessage = with_resolver(&StrictTypeUrls(GlobalRegistry), || serde_json::from_str(&json))?;
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StrictTypeUrls<R>(pub R);
+
+impl<R> TypeResolver for StrictTypeUrls<R>
+where
+    R: TypeResolver,
+{
+    fn resolve(&self, type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+        // Resolvers prefer the exact match, so any other entry means there is none.
+        self.0
+            .resolve(type_url)
+            .filter(|entry| entry.type_url == type_url)
+    }
+
+    fn strict_type_urls(&self) -> bool {
+        true
+    }
+}
+
+thread_local! {
+    static SCOPED: Cell<Option<NonNull<dyn TypeResolver>>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with the given resolver in place of the global registry. Calls can be nested, the
+/// innermost resolver is used.
+pub fn with_resolver<T>(resolver: &dyn TypeResolver, f: impl FnOnce() -> T) -> T {
+    // Restores the previous resolver when `f` returns or unwinds.
+    struct Restore(Option<NonNull<dyn TypeResolver>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED.with(|scoped| scoped.set(self.0));
+        }
+    }
+
+    // SAFETY: only the lifetime is erased. The pointer is only dereferenced by `resolve` while
+    // `f` runs, during which `resolver` is borrowed, and it is removed again before returning.
+    let resolver: NonNull<dyn TypeResolver> = unsafe {
+        std::mem::transmute::<NonNull<dyn TypeResolver + '_>, NonNull<dyn TypeResolver + 'static>>(
+            NonNull::from(resolver),
+        )
+    };
+    let _restore = Restore(SCOPED.with(|scoped| scoped.replace(Some(resolver))));
+    f()
+}
+
+/// Returns whether a resolver was put in place with [`with_resolver`].
+pub fn is_scoped() -> bool {
+    SCOPED.with(|scoped| scoped.get().is_some())
+}
+
+/// Resolves the type url with the resolver put in place with [`with_resolver`], or with the
+/// global registry otherwise.
+pub fn resolve(type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+    match SCOPED.with(Cell::get) {
+        // SAFETY: see `with_resolver`.
+        Some(resolver) => unsafe { resolver.as_ref() }.resolve(type_url),
+        None => registry::resolve(type_url),
+    }
+}
+
+/// Returns whether only exact type urls are accepted, either by the resolver put in place with
+/// [`with_resolver`] or for the whole process with [`registry::set_strict_type_urls`].
+pub fn strict_type_urls() -> bool {
+    let scoped = match SCOPED.with(Cell::get) {
+        // SAFETY: see `with_resolver`.
+        Some(resolver) => unsafe { resolver.as_ref() }.strict_type_urls(),
+        None => false,
+    };
+    scoped || registry::strict_type_urls()
+}
+
+/// Serializes the message with the given resolver in place, see [`with_resolver`].
+pub struct WithResolver<'a, T: ?Sized>(pub &'a dyn TypeResolver, pub &'a T);
+
+impl<T> Serialize for WithResolver<'_, T>
+where
+    T: Serialize + ?Sized,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        with_resolver(self.0, || self.1.serialize(serializer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Only(&'static MessageSerdeDecoderEntry);
+
+    impl TypeResolver for Only {
+        fn resolve(&self, type_url: &str) -> Option<&'static MessageSerdeDecoderEntry> {
+            (self.0.type_url == type_url).then_some(self.0)
+        }
+    }
+
+    #[allow(deprecated)]
+    fn entry(type_url: &'static str) -> &'static MessageSerdeDecoderEntry {
+        Box::leak(Box::new(MessageSerdeDecoderEntry::new(type_url, |_| {
+            Err(prost::DecodeError::new("not decodable"))
+        })))
+    }
+
+    #[test]
+    fn nest_scoped_resolvers() {
+        let a = Only(entry("example.com/resolver.test.A"));
+        let b = Only(entry("example.com/resolver.test.B"));
+        assert!(!is_scoped());
+        assert!(resolve("example.com/resolver.test.A").is_none());
+
+        with_resolver(&a, || {
+            assert!(is_scoped());
+            assert!(resolve("example.com/resolver.test.A").is_some());
+            with_resolver(&b, || {
+                assert!(resolve("example.com/resolver.test.A").is_none());
+                assert!(resolve("example.com/resolver.test.B").is_some());
+            });
+            assert!(resolve("example.com/resolver.test.A").is_some());
+        });
+        assert!(!is_scoped());
+    }
+
+    #[test]
+    fn resolve_with_registry() {
+        let mut registry = Registry::new();
+        registry
+            .register(entry("type.googleapis.com/resolver.test.C"))
+            .unwrap();
+        with_resolver(&registry, || {
+            assert!(resolve("example.com/resolver.test.C").is_some());
+            assert!(resolve("example.com/resolver.test.D").is_none());
+        });
+    }
+
+    #[test]
+    fn resolve_strict_type_urls() {
+        let mut registry = Registry::new();
+        registry
+            .register(entry("type.googleapis.com/resolver.test.E"))
+            .unwrap();
+        let strict = StrictTypeUrls(registry);
+        assert!(strict
+            .resolve("type.googleapis.com/resolver.test.E")
+            .is_some());
+        assert!(strict.resolve("example.com/resolver.test.E").is_none());
+        assert!(strict.0.resolve("example.com/resolver.test.E").is_some());
+
+        assert!(!strict_type_urls());
+        with_resolver(&strict, || {
+            assert!(strict_type_urls());
+            with_resolver(&strict.0, || assert!(!strict_type_urls()));
+        });
+    }
+}